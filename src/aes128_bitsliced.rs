@@ -0,0 +1,286 @@
+use crate::aes_fhe::check_params;
+use crate::aes128_bool_circ::{
+    INV_MIX_COLS_CIRCUIT, MIX_COLS_CIRCUIT, PosVals, get_bool_from_u8, get_u8_from_bool, sbox_bc,
+    sbox_inv_bc,
+};
+use crate::aes128_keyschedule::{BLOCKSIZE, KeySize};
+use crate::aes128_rdx_fhe::{flatten_states, split_states};
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use crate::aes128_tables::{INV_SHIFT_ROWS, SHIFT_ROWS, permute_blocks};
+use crate::bool_circ::BoolCircuit;
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
+
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::integer::{BooleanBlock, ServerKey};
+use tfhe::shortint::Ciphertext;
+
+use rayon::prelude::*;
+
+// the state as 8 BooleanBlocks per byte, lsb first
+pub fn state_to_bc(
+    state: &[BaseRadixCiphertext<Ciphertext>],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> Vec<[BooleanBlock; 8]> {
+    state
+        .par_iter()
+        .map(|byte| get_bool_from_u8(byte, pos_vals, sk))
+        .collect()
+}
+
+pub fn state_from_bc(
+    bits: &[[BooleanBlock; 8]],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> Vec<BaseRadixCiphertext<Ciphertext>> {
+    bits.par_iter()
+        .map(|byte| get_u8_from_bool(byte.clone(), pos_vals, sk))
+        .collect()
+}
+
+// the state may hold a batch of blocks, all under the same round key
+#[inline]
+pub fn add_round_key_bc(
+    state: &mut [[BooleanBlock; 8]],
+    rkey: &[[BooleanBlock; 8]],
+    sk: &ServerKey,
+) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+    if rkey.len() != BLOCKSIZE {
+        return Err(AesFheError::RoundKeyLength(rkey.len()));
+    }
+
+    state.par_iter_mut().enumerate().for_each(|(i, byte)| {
+        byte.par_iter_mut()
+            .zip(rkey[i % BLOCKSIZE].par_iter())
+            .for_each(|(bit, k_bit)| {
+                *bit = sk.boolean_bitxor(bit, k_bit);
+            });
+    });
+
+    Ok(())
+}
+
+#[inline]
+pub fn sub_bytes_bc(state: &mut [[BooleanBlock; 8]], sk: &ServerKey) {
+    state.par_iter_mut().for_each(|byte| {
+        *byte = sbox_bc(byte, sk);
+    });
+}
+
+#[inline]
+pub fn inv_sub_bytes_bc(state: &mut [[BooleanBlock; 8]], sk: &ServerKey) {
+    state.par_iter_mut().for_each(|byte| {
+        *byte = sbox_inv_bc(byte, sk);
+    });
+}
+
+// a permutation of the wires, no bootstrap, every block of the batch
+#[inline]
+pub fn shift_rows_bc(state: &mut [[BooleanBlock; 8]]) {
+    permute_blocks(state, &SHIFT_ROWS);
+}
+
+#[inline]
+pub fn inv_shift_rows_bc(state: &mut [[BooleanBlock; 8]]) {
+    permute_blocks(state, &INV_SHIFT_ROWS);
+}
+
+#[inline]
+pub fn mix_columns_bc(state: &mut [[BooleanBlock; 8]], sk: &ServerKey) -> Result<(), AesFheError> {
+    columns_bc(state, &MIX_COLS_CIRCUIT, sk)
+}
+
+#[inline]
+pub fn inv_mix_columns_bc(
+    state: &mut [[BooleanBlock; 8]],
+    sk: &ServerKey,
+) -> Result<(), AesFheError> {
+    columns_bc(state, &INV_MIX_COLS_CIRCUIT, sk)
+}
+
+#[inline]
+fn columns_bc(
+    state: &mut [[BooleanBlock; 8]],
+    circuit: &BoolCircuit,
+    sk: &ServerKey,
+) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+
+    state.par_chunks_exact_mut(4).try_for_each(|col| {
+        let x: Vec<BooleanBlock> = col.iter().flatten().cloned().collect();
+        let y = circuit.eval(&x, sk)?;
+
+        for (row, out) in col.iter_mut().zip(y.chunks_exact(8)) {
+            *row = core::array::from_fn(|j| out[j].clone());
+        }
+
+        Ok(())
+    })
+}
+
+// the batch of states and the round keys, the number of rounds
+#[inline]
+fn check_bits(state: &[[BooleanBlock; 8]], xk: &[[BooleanBlock; 8]]) -> Result<usize, AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+    let key_size =
+        KeySize::from_round_key_size(xk.len()).ok_or(AesFheError::KeyLength(xk.len()))?;
+
+    Ok(key_size.rounds())
+}
+
+// the steps and rounds are named as in aes128_rdx_fhe, so the metrics of both
+// modes line up
+pub fn encrypt_bits_bc(
+    state: &[[BooleanBlock; 8]],
+    xk: &[[BooleanBlock; 8]],
+    m: &mut Metrics,
+    sk: &ServerKey,
+) -> Result<Vec<[BooleanBlock; 8]>, AesFheError> {
+    let rounds = check_bits(state, xk)?;
+    let round_key = |round: usize| &xk[round * BLOCKSIZE..(round + 1) * BLOCKSIZE];
+
+    let mut state_bc = state.to_vec();
+
+    m.round(|m| {
+        m.time("add_round_key", || {
+            add_round_key_bc(&mut state_bc, round_key(0), sk)
+        })
+    })?;
+
+    for round in 1..rounds {
+        m.round(|m| {
+            m.time("sub_bytes", || sub_bytes_bc(&mut state_bc, sk));
+            m.time("shift_rows", || shift_rows_bc(&mut state_bc));
+            m.time("mix_columns", || mix_columns_bc(&mut state_bc, sk))?;
+            m.time("add_round_key", || {
+                add_round_key_bc(&mut state_bc, round_key(round), sk)
+            })
+        })?;
+    }
+
+    m.round(|m| {
+        m.time("sub_bytes", || sub_bytes_bc(&mut state_bc, sk));
+        m.time("shift_rows", || shift_rows_bc(&mut state_bc));
+        m.time("add_round_key", || {
+            add_round_key_bc(&mut state_bc, round_key(rounds), sk)
+        })
+    })?;
+
+    Ok(state_bc)
+}
+
+pub fn decrypt_bits_bc(
+    state: &[[BooleanBlock; 8]],
+    xk: &[[BooleanBlock; 8]],
+    m: &mut Metrics,
+    sk: &ServerKey,
+) -> Result<Vec<[BooleanBlock; 8]>, AesFheError> {
+    let rounds = check_bits(state, xk)?;
+    let round_key = |round: usize| &xk[round * BLOCKSIZE..(round + 1) * BLOCKSIZE];
+
+    let mut state_bc = state.to_vec();
+
+    m.round(|m| {
+        m.time("add_round_key", || {
+            add_round_key_bc(&mut state_bc, round_key(rounds), sk)
+        })
+    })?;
+
+    for round in (1..rounds).rev() {
+        m.round(|m| {
+            m.time("inv_shift_rows", || inv_shift_rows_bc(&mut state_bc));
+            m.time("inv_sub_bytes", || inv_sub_bytes_bc(&mut state_bc, sk));
+            m.time("add_round_key", || {
+                add_round_key_bc(&mut state_bc, round_key(round), sk)
+            })?;
+            m.time("inv_mix_columns", || inv_mix_columns_bc(&mut state_bc, sk))
+        })?;
+    }
+
+    m.round(|m| {
+        m.time("inv_shift_rows", || inv_shift_rows_bc(&mut state_bc));
+        m.time("inv_sub_bytes", || inv_sub_bytes_bc(&mut state_bc, sk));
+        m.time("add_round_key", || {
+            add_round_key_bc(&mut state_bc, round_key(0), sk)
+        })
+    })?;
+
+    Ok(state_bc)
+}
+
+// radix in and out, the rounds never leave the boolean representation
+pub fn encrypt_state_bitsliced_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<EncryptedState, AesFheError> {
+    let (mut states, _) = encrypt_states_bitsliced_with_fhe(std::slice::from_ref(state), xk, sk)?;
+
+    Ok(states.remove(0))
+}
+
+pub fn decrypt_state_bitsliced_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<EncryptedState, AesFheError> {
+    let (mut states, _) = decrypt_states_bitsliced_with_fhe(std::slice::from_ref(state), xk, sk)?;
+
+    Ok(states.remove(0))
+}
+
+// the whole batch goes through the rounds side by side, like
+// encrypt_states_with_fhe; the conversions to and from bits are steps of the
+// run outside the rounds
+pub fn encrypt_states_bitsliced_with_fhe(
+    states: &[EncryptedState],
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    bitsliced_fhe(states, xk, encrypt_bits_bc, sk)
+}
+
+pub fn decrypt_states_bitsliced_with_fhe(
+    states: &[EncryptedState],
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    bitsliced_fhe(states, xk, decrypt_bits_bc, sk)
+}
+
+type BitsRounds = fn(
+    &[[BooleanBlock; 8]],
+    &[[BooleanBlock; 8]],
+    &mut Metrics,
+    &ServerKey,
+) -> Result<Vec<[BooleanBlock; 8]>, AesFheError>;
+
+fn bitsliced_fhe(
+    states: &[EncryptedState],
+    xk: &EncryptedKeySchedule,
+    rounds: BitsRounds,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    if states.is_empty() {
+        return Ok((Vec::new(), Metrics::new()));
+    }
+
+    let batch = flatten_states(states);
+    check_params(&batch, sk)?;
+    check_params(xk, sk)?;
+
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut metrics = Metrics::new();
+
+    let batch = metrics.run(|m| {
+        let state_bc = m.time("to_bits", || state_to_bc(&batch, &pos_vals, sk));
+        let xk_bc = m.time("to_bits", || state_to_bc(xk, &pos_vals, sk));
+        let state_bc = rounds(&state_bc, &xk_bc, m, sk)?;
+
+        Ok::<_, AesFheError>(m.time("from_bits", || state_from_bc(&state_bc, &pos_vals, sk)))
+    })?;
+
+    Ok((split_states(batch)?, metrics))
+}
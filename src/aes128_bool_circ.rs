@@ -1,20 +1,21 @@
+use crate::aes_fhe::num_blocks;
+use crate::bool_circ::{BoolCircuit, wire_names};
+use crate::error::AesFheError;
+
 use tfhe::shortint::Ciphertext;
 
 use tfhe::integer::ciphertext::BaseRadixCiphertext;
 use tfhe::integer::prelude::ServerKeyDefaultCMux;
 use tfhe::integer::{BooleanBlock, RadixClientKey, ServerKey};
 
-use std::collections::HashMap;
-use std::sync::atomic::AtomicI32;
-use std::sync::atomic::Ordering::Relaxed;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std::sync::LazyLock;
 
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 const PS: [u8; 8] = [1, 2, 4, 8, 16, 32, 64, 128];
 
+#[derive(Serialize, Deserialize)]
 pub struct PosVals {
     p_b: [BaseRadixCiphertext<Ciphertext>; 8],
     v_0: BaseRadixCiphertext<Ciphertext>,
@@ -27,6 +28,13 @@ impl PosVals {
         PosVals { p_b, v_0 }
     }
 
+    // the constants are public, so the server can build them without the client key
+    pub fn new_trivial(sk: &ServerKey) -> Self {
+        let p_b = core::array::from_fn(|i| sk.create_trivial_radix(PS[i], num_blocks(sk)));
+        let v_0 = sk.create_trivial_radix(0u8, num_blocks(sk));
+        PosVals { p_b, v_0 }
+    }
+
     #[inline]
     pub fn get_p_b(&self) -> &[BaseRadixCiphertext<Ciphertext>; 8] {
         &self.p_b
@@ -57,27 +65,25 @@ impl ColBoolBlocks {
 }
 
 #[inline]
-fn get_bool_from_u8(
+pub fn get_bool_from_u8(
     idx: &BaseRadixCiphertext<Ciphertext>,
     pos_vals: &PosVals,
     sk: &ServerKey,
 ) -> [BooleanBlock; 8] {
     let p_b = pos_vals.get_p_b();
 
-    let x_p: Vec<BooleanBlock> = (0..8)
-        .into_par_iter()
-        .map(|i| {
-            let mask = sk.bitand_parallelized(&p_b[i], idx);
-            sk.eq_parallelized(&mask, &p_b[i])
-        })
-        .collect();
+    let mut x_p: [BooleanBlock; 8] =
+        core::array::from_fn(|_| sk.create_trivial_boolean_block(false));
+    x_p.par_iter_mut().enumerate().for_each(|(i, bit)| {
+        let mask = sk.bitand_parallelized(&p_b[i], idx);
+        *bit = sk.eq_parallelized(&mask, &p_b[i]);
+    });
 
-    x_p.try_into()
-        .unwrap_or_else(|_| panic!("Failed to convert Vec to array"))
+    x_p
 }
 
 #[inline]
-fn get_u8_from_bool(
+pub fn get_u8_from_bool(
     res_p: [BooleanBlock; 8],
     pos_vals: &PosVals,
     sk: &ServerKey,
@@ -94,6 +100,39 @@ fn get_u8_from_bool(
         .reduce(|| v_0.clone(), |a, b| sk.bitor_parallelized(&a, &b))
 }
 
+// bit 0 is the msb of byte 0, the bit order of SP 800-38B and SP 800-38D
+pub fn get_bits_from_block(
+    block: &[BaseRadixCiphertext<Ciphertext>],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> Vec<BooleanBlock> {
+    block
+        .par_iter()
+        .flat_map_iter(|byte| get_bool_from_u8(byte, pos_vals, sk).into_iter().rev())
+        .collect()
+}
+
+pub fn get_block_from_bits(
+    bits: &[BooleanBlock],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    if bits.is_empty() || !bits.len().is_multiple_of(8) {
+        return Err(AesFheError::BitLength {
+            expected: bits.len().next_multiple_of(8).max(8),
+            actual: bits.len(),
+        });
+    }
+
+    Ok(bits
+        .par_chunks_exact(8)
+        .map(|msb_first| {
+            let lsb_first: [BooleanBlock; 8] = core::array::from_fn(|i| msb_first[7 - i].clone());
+            get_u8_from_bool(lsb_first, pos_vals, sk)
+        })
+        .collect())
+}
+
 #[inline]
 pub fn sbox_idx(
     idx: &BaseRadixCiphertext<Ciphertext>,
@@ -111,6 +150,25 @@ pub fn mix_cols(
     col: &[BaseRadixCiphertext<Ciphertext>; 4],
     pos_vals: &PosVals,
     sk: &ServerKey,
+) -> [BaseRadixCiphertext<Ciphertext>; 4] {
+    col_circuit(&MIX_COLS_CIRCUIT, col, pos_vals, sk)
+}
+
+#[inline]
+pub fn inv_mix_cols(
+    col: &[BaseRadixCiphertext<Ciphertext>; 4],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> [BaseRadixCiphertext<Ciphertext>; 4] {
+    col_circuit(&INV_MIX_COLS_CIRCUIT, col, pos_vals, sk)
+}
+
+#[inline]
+fn col_circuit(
+    circuit: &BoolCircuit,
+    col: &[BaseRadixCiphertext<Ciphertext>; 4],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
 ) -> [BaseRadixCiphertext<Ciphertext>; 4] {
     let r1_p = get_bool_from_u8(&col[0], pos_vals, sk);
     let r2_p = get_bool_from_u8(&col[1], pos_vals, sk);
@@ -118,7 +176,8 @@ pub fn mix_cols(
     let r4_p = get_bool_from_u8(&col[3], pos_vals, sk);
 
     let inp = ColBoolBlocks::new([r1_p, r2_p, r3_p, r4_p]);
-    let res_p = mix_cols_bc(&inp, sk);
+    // only the built-in 32 bit circuits come here, see mix_cols_bc
+    let res_p = mix_cols_bc(circuit, &inp, sk).expect("built-in column circuit");
 
     let out1 = get_u8_from_bool(res_p.r1, pos_vals, sk);
     let out2 = get_u8_from_bool(res_p.r2, pos_vals, sk);
@@ -140,111 +199,32 @@ pub fn sbox_inv_idx(
     get_u8_from_bool(res_p, pos_vals, sk)
 }
 
+// any 8 bit to 8 bit circuit with the bits msb first, such as an S-box
+// loaded with BoolCircuit::load
 #[inline]
-fn sbox_bc(inp: &[BooleanBlock; 8], sk: &ServerKey) -> [BooleanBlock; 8] {
-    let map = HashMap::<String, BooleanBlock>::new();
-    let var = Arc::new(Mutex::new(map));
-
-    // reverse order
-    for i in 0..=7 {
-        var.lock()
-            .unwrap()
-            .insert(format!("x{}", i), inp[7 - i].clone());
+pub fn byte_circuit_idx(
+    idx: &BaseRadixCiphertext<Ciphertext>,
+    circuit: &BoolCircuit,
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> Result<BaseRadixCiphertext<Ciphertext>, AesFheError> {
+    if circuit.num_inputs() != 8 || circuit.num_outputs() != 8 {
+        return Err(AesFheError::InvalidCircuit(format!(
+            "{} inputs and {} outputs, a byte circuit has 8 and 8",
+            circuit.num_inputs(),
+            circuit.num_outputs()
+        )));
     }
 
-    let vlen = SBOX_INSTR.len();
-    let idx = &AtomicI32::new(0);
-
-    thread::scope(|s| {
-        for _ in 0..std::cmp::min(8, num_cpus::get()) {
-            let sk_clone = sk.clone();
-            let instr_clone = SBOX_INSTR;
-            let var_clone = var.clone();
-
-            s.spawn(move || {
-                loop {
-                    let b = idx.fetch_add(1, Relaxed);
-                    if b >= vlen.try_into().unwrap() {
-                        break;
-                    }
-
-                    let st = instr_clone.get(b as usize).unwrap();
-                    let tokens = st.split(' ').collect::<Vec<_>>();
-
-                    let op = tokens[3];
-                    let name = tokens[0];
-
-                    let value = match op {
-                        "^" => {
-                            loop {
-                                let var2 = var_clone.lock().unwrap();
-                                if !(var2.contains_key(tokens[2]) && var2.contains_key(tokens[4])) {
-                                    drop(var2);
-                                    thread::sleep(Duration::from_micros(100));
-                                } else {
-                                    break;
-                                }
-                            }
-                            let var2 = var_clone.lock().unwrap();
-                            let t0 = &var2[tokens[2]].clone();
-                            let t1 = &var2[tokens[4]].clone();
-                            drop(var2);
-                            sk_clone.boolean_bitxor(t0, t1)
-                        }
-                        "&" => {
-                            loop {
-                                let var2 = var_clone.lock().unwrap();
-                                if !(var2.contains_key(tokens[2]) && var2.contains_key(tokens[4])) {
-                                    drop(var2);
-                                    thread::sleep(Duration::from_micros(100));
-                                } else {
-                                    break;
-                                }
-                            }
-                            let var2 = var_clone.lock().unwrap();
-                            let t0 = &var2[tokens[2]].clone();
-                            let t1 = &var2[tokens[4]].clone();
-                            drop(var2);
-                            sk_clone.boolean_bitand(t0, t1)
-                        }
-                        "!" => {
-                            loop {
-                                let var2 = var_clone.lock().unwrap();
-                                if !(var2.contains_key(tokens[2])) {
-                                    drop(var2);
-                                    thread::sleep(Duration::from_micros(100));
-                                } else {
-                                    break;
-                                }
-                            }
-                            let var2 = var_clone.lock().unwrap();
-                            let t0 = &var2[tokens[2]].clone();
-                            drop(var2);
-                            sk_clone.boolean_bitnot(t0)
-                        }
-                        &_ => todo!(),
-                    };
-                    let mut var2 = var_clone.lock().unwrap();
-                    var2.insert(name.to_string(), value);
-                }
-            });
-        }
-    });
+    let x_p = get_bool_from_u8(idx, pos_vals, sk);
+    let res_p = eval_byte_bc(circuit, &x_p, sk)?;
 
-    // reverse order
-    let out: [BooleanBlock; 8] = (0..8)
-        .map(|i| {
-            var.lock()
-                .unwrap()
-                .get(&format!("s{}", 7 - i))
-                .cloned()
-                .unwrap()
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
+    Ok(get_u8_from_bool(res_p, pos_vals, sk))
+}
 
-    out.clone()
+#[inline]
+pub fn sbox_bc(inp: &[BooleanBlock; 8], sk: &ServerKey) -> [BooleanBlock; 8] {
+    eval_byte_bc(&SBOX_CIRCUIT, inp, sk).expect("built-in S-box circuit")
 }
 
 /*
@@ -388,269 +368,87 @@ fn sbox_bc(inp: &[BooleanBlock; 8], sk: &ServerKey) -> [BooleanBlock; 8] {
 */
 
 #[inline]
-fn sbox_inv_bc(inp: &[BooleanBlock; 8], sk: &ServerKey) -> [BooleanBlock; 8] {
-    // reverse order
-    let u0 = &inp[7];
-    let u1 = &inp[6];
-    let u2 = &inp[5];
-    let u3 = &inp[4];
-    let u4 = &inp[3];
-    let u5 = &inp[2];
-    let u6 = &inp[1];
-    let u7 = &inp[0];
-
-    let y0 = sk.boolean_bitxor(u0, u3);
-    let y2 = sk.boolean_bitnot(&sk.boolean_bitxor(u1, u3));
-    let y4 = sk.boolean_bitxor(u0, &y2);
-    let rtl0 = sk.boolean_bitxor(u6, u7);
-    let y1 = sk.boolean_bitxor(&y2, &rtl0);
-    let y7 = sk.boolean_bitnot(&sk.boolean_bitxor(u2, &y1));
-    let rtl1 = sk.boolean_bitxor(u3, u4);
-    let y6 = sk.boolean_bitnot(&sk.boolean_bitxor(u7, &rtl1));
-    let y3 = sk.boolean_bitxor(&y1, &rtl1);
-    let rtl2 = sk.boolean_bitnot(&sk.boolean_bitxor(u0, u2));
-    let y5 = sk.boolean_bitxor(u5, &rtl2);
-    let sa1 = sk.boolean_bitxor(&y0, &y2);
-    let sa0 = sk.boolean_bitxor(&y1, &y3);
-    let sb1 = sk.boolean_bitxor(&y4, &y6);
-    let sb0 = sk.boolean_bitxor(&y5, &y7);
-    let ah = sk.boolean_bitxor(&y0, &y1);
-    let al = sk.boolean_bitxor(&y2, &y3);
-    let aa = sk.boolean_bitxor(&sa0, &sa1);
-    let bh = sk.boolean_bitxor(&y4, &y5);
-    let bl = sk.boolean_bitxor(&y6, &y7);
-    let bb = sk.boolean_bitxor(&sb0, &sb1);
-    let ab20 = sk.boolean_bitxor(&sa0, &sb0);
-    let ab22 = sk.boolean_bitxor(&al, &bl);
-    let ab23 = sk.boolean_bitxor(&y3, &y7);
-    let ab21 = sk.boolean_bitxor(&sa1, &sb1);
-    let abcd1 = sk.boolean_bitand(&ah, &bh);
-    let rr1 = sk.boolean_bitand(&y0, &y4);
-    let ph11 = sk.boolean_bitxor(&ab20, &abcd1);
-    let t01 = sk.boolean_bitand(&y1, &y5);
-    let ph01 = sk.boolean_bitxor(&t01, &abcd1);
-    let abcd2 = sk.boolean_bitand(&al, &bl);
-    let r1 = sk.boolean_bitand(&y2, &y6);
-    let pl11 = sk.boolean_bitxor(&ab22, &abcd2);
-    let r2 = sk.boolean_bitand(&y3, &y7);
-    let pl01 = sk.boolean_bitxor(&r2, &abcd2);
-    let r3 = sk.boolean_bitand(&sa0, &sb0);
-    let vr1 = sk.boolean_bitand(&aa, &bb);
-    let pr1 = sk.boolean_bitxor(&vr1, &r3);
-    let wr1 = sk.boolean_bitand(&sa1, &sb1);
-    let qr1 = sk.boolean_bitxor(&wr1, &r3);
-    let ab0 = sk.boolean_bitxor(&ph11, &rr1);
-    let ab1 = sk.boolean_bitxor(&ph01, &ab21);
-    let ab2 = sk.boolean_bitxor(&pl11, &r1);
-    let ab3 = sk.boolean_bitxor(&pl01, &qr1);
-    let cp1 = sk.boolean_bitxor(&ab0, &pr1);
-    let cp2 = sk.boolean_bitxor(&ab1, &qr1);
-    let cp3 = sk.boolean_bitxor(&ab2, &pr1);
-    let cp4 = sk.boolean_bitxor(&ab3, &ab23);
-    let tinv1 = sk.boolean_bitxor(&cp3, &cp4);
-    let tinv2 = sk.boolean_bitand(&cp3, &cp1);
-    let tinv3 = sk.boolean_bitxor(&cp2, &tinv2);
-    let tinv4 = sk.boolean_bitxor(&cp1, &cp2);
-    let tinv5 = sk.boolean_bitxor(&cp4, &tinv2);
-    let tinv6 = sk.boolean_bitand(&tinv5, &tinv4);
-    let tinv7 = sk.boolean_bitand(&tinv3, &tinv1);
-    let d2 = sk.boolean_bitxor(&cp4, &tinv7);
-    let d0 = sk.boolean_bitxor(&cp2, &tinv6);
-    let tinv8 = sk.boolean_bitand(&cp1, &cp4);
-    let tinv9 = sk.boolean_bitand(&tinv4, &tinv8);
-    let tinv10 = sk.boolean_bitxor(&tinv4, &tinv2);
-    let d1 = sk.boolean_bitxor(&tinv9, &tinv10);
-    let tinv11 = sk.boolean_bitand(&cp2, &cp3);
-    let tinv12 = sk.boolean_bitand(&tinv1, &tinv11);
-    let tinv13 = sk.boolean_bitxor(&tinv1, &tinv2);
-    let d3 = sk.boolean_bitxor(&tinv12, &tinv13);
-    let sd1 = sk.boolean_bitxor(&d1, &d3);
-    let sd0 = sk.boolean_bitxor(&d0, &d2);
-    let dl = sk.boolean_bitxor(&d0, &d1);
-    let dh = sk.boolean_bitxor(&d2, &d3);
-    let dd = sk.boolean_bitxor(&sd0, &sd1);
-    let abcd3 = sk.boolean_bitand(&dh, &bh);
-    let rr2 = sk.boolean_bitand(&d3, &y4);
-    let t02 = sk.boolean_bitand(&d2, &y5);
-    let abcd4 = sk.boolean_bitand(&dl, &bl);
-    let r4 = sk.boolean_bitand(&d1, &y6);
-    let r5 = sk.boolean_bitand(&d0, &y7);
-    let r6 = sk.boolean_bitand(&sd0, &sb0);
-    let vr2 = sk.boolean_bitand(&dd, &bb);
-    let wr2 = sk.boolean_bitand(&sd1, &sb1);
-    let abcd5 = sk.boolean_bitand(&dh, &ah);
-    let r7 = sk.boolean_bitand(&d3, &y0);
-    let r8 = sk.boolean_bitand(&d2, &y1);
-    let abcd6 = sk.boolean_bitand(&dl, &al);
-    let r9 = sk.boolean_bitand(&d1, &y2);
-    let r10 = sk.boolean_bitand(&d0, &y3);
-    let r11 = sk.boolean_bitand(&sd0, &sa0);
-    let vr3 = sk.boolean_bitand(&dd, &aa);
-    let wr3 = sk.boolean_bitand(&sd1, &sa1);
-    let ph12 = sk.boolean_bitxor(&rr2, &abcd3);
-    let ph02 = sk.boolean_bitxor(&t02, &abcd3);
-    let pl12 = sk.boolean_bitxor(&r4, &abcd4);
-    let pl02 = sk.boolean_bitxor(&r5, &abcd4);
-    let pr2 = sk.boolean_bitxor(&vr2, &r6);
-    let qr2 = sk.boolean_bitxor(&wr2, &r6);
-    let p0 = sk.boolean_bitxor(&ph12, &pr2);
-    let p1 = sk.boolean_bitxor(&ph02, &qr2);
-    let p2 = sk.boolean_bitxor(&pl12, &pr2);
-    let p3 = sk.boolean_bitxor(&pl02, &qr2);
-    let ph13 = sk.boolean_bitxor(&r7, &abcd5);
-    let ph03 = sk.boolean_bitxor(&r8, &abcd5);
-    let pl13 = sk.boolean_bitxor(&r9, &abcd6);
-    let pl03 = sk.boolean_bitxor(&r10, &abcd6);
-    let pr3 = sk.boolean_bitxor(&vr3, &r11);
-    let qr3 = sk.boolean_bitxor(&wr3, &r11);
-    let p4 = sk.boolean_bitxor(&ph13, &pr3);
-    let s7 = sk.boolean_bitxor(&ph03, &qr3);
-    let p6 = sk.boolean_bitxor(&pl13, &pr3);
-    let p7 = sk.boolean_bitxor(&pl03, &qr3);
-    let s3 = sk.boolean_bitxor(&p1, &p6);
-    let s6 = sk.boolean_bitxor(&p2, &p6);
-    let s0 = sk.boolean_bitxor(&p3, &p6);
-    let x11 = sk.boolean_bitxor(&p0, &p2);
-    let s5 = sk.boolean_bitxor(&s0, &x11);
-    let x13 = sk.boolean_bitxor(&p4, &p7);
-    let x14 = sk.boolean_bitxor(&x11, &x13);
-    let s1 = sk.boolean_bitxor(&s3, &x14);
-    let x16 = sk.boolean_bitxor(&p1, &s7);
-    let s2 = sk.boolean_bitxor(&x14, &x16);
-    let x18 = sk.boolean_bitxor(&p0, &p4);
-    let x19 = sk.boolean_bitxor(&s5, &x16);
-    let s4 = sk.boolean_bitxor(&x18, &x19);
+pub fn sbox_inv_bc(inp: &[BooleanBlock; 8], sk: &ServerKey) -> [BooleanBlock; 8] {
+    eval_byte_bc(&SBOX_INV_CIRCUIT, inp, sk).expect("built-in S-box circuit")
+}
 
+// the S-box circuits read and write the bits msb first
+#[inline]
+fn eval_byte_bc(
+    circuit: &BoolCircuit,
+    inp: &[BooleanBlock; 8],
+    sk: &ServerKey,
+) -> Result<[BooleanBlock; 8], AesFheError> {
     // reverse order
-    let out: [BooleanBlock; 8] = [s7, s6, s5, s4, s3, s2, s1, s0];
+    let x: Vec<BooleanBlock> = inp.iter().rev().cloned().collect();
+    let out = circuit.eval(&x, sk)?;
+    check_outputs(&out, 8)?;
 
-    out.clone()
+    Ok(core::array::from_fn(|i| out[7 - i].clone()))
 }
 
 #[inline]
-fn mix_cols_bc(col: &ColBoolBlocks, sk: &ServerKey) -> ColBoolBlocks {
-    let map = HashMap::<String, BooleanBlock>::new();
-    let var = Arc::new(Mutex::new(map));
-
-    for i in 0..=7 {
-        var.lock()
-            .unwrap()
-            .insert(format!("x{}", i), col.r1[i].clone());
-    }
+fn mix_cols_bc(
+    circuit: &BoolCircuit,
+    col: &ColBoolBlocks,
+    sk: &ServerKey,
+) -> Result<ColBoolBlocks, AesFheError> {
+    let x: Vec<BooleanBlock> = [&col.r1, &col.r2, &col.r3, &col.r4]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    let y = circuit.eval(&x, sk)?;
+    check_outputs(&y, 32)?;
 
-    for i in 0..=7 {
-        var.lock()
-            .unwrap()
-            .insert(format!("x{}", i + 8), col.r2[i].clone());
-    }
+    let row = |i: usize| -> [BooleanBlock; 8] { core::array::from_fn(|j| y[8 * i + j].clone()) };
 
-    for i in 0..=7 {
-        var.lock()
-            .unwrap()
-            .insert(format!("x{}", i + 16), col.r3[i].clone());
-    }
+    Ok(ColBoolBlocks::new([row(0), row(1), row(2), row(3)]))
+}
 
-    for i in 0..=7 {
-        var.lock()
-            .unwrap()
-            .insert(format!("x{}", i + 24), col.r4[i].clone());
+fn check_outputs(out: &[BooleanBlock], expected: usize) -> Result<(), AesFheError> {
+    if out.len() != expected {
+        return Err(AesFheError::InvalidCircuit(format!(
+            "{} output bits, expected {}",
+            out.len(),
+            expected
+        )));
     }
 
-    let vlen = MIX_COLS_INSTR.len();
-    let idx = &AtomicI32::new(0);
-
-    thread::scope(|s| {
-        for _ in 0..std::cmp::min(8, num_cpus::get()) {
-            let sk_clone = sk.clone();
-            let instr_clone = MIX_COLS_INSTR;
-            let var_clone = var.clone();
-
-            s.spawn(move || {
-                loop {
-                    let b = idx.fetch_add(1, Relaxed);
-                    if b >= vlen.try_into().unwrap() {
-                        break;
-                    }
-                    let st = instr_clone.get(b as usize).unwrap();
-                    let tokens = st.split(' ').collect::<Vec<_>>();
-
-                    let op = tokens[3];
-                    let name = tokens[0];
-
-                    let value = match op {
-                        "^" => {
-                            loop {
-                                let var2 = var_clone.lock().unwrap();
-                                if !(var2.contains_key(tokens[2]) && var2.contains_key(tokens[4])) {
-                                    drop(var2);
-                                    thread::sleep(Duration::from_micros(100));
-                                } else {
-                                    break;
-                                }
-                            }
-                            let var2 = var_clone.lock().unwrap();
-                            let t0 = &var2[tokens[2]].clone();
-                            let t1 = &var2[tokens[4]].clone();
-                            drop(var2);
-                            sk_clone.boolean_bitxor(t0, t1)
-                        }
-                        &_ => todo!(),
-                    };
-                    let mut var2 = var_clone.lock().unwrap();
-                    var2.insert(name.to_string(), value);
-                }
-            });
-        }
-    });
-
-    let r1: [BooleanBlock; 8] = (0..8)
-        .map(|i| {
-            var.lock()
-                .unwrap()
-                .get(&format!("y{}", i))
-                .cloned()
-                .unwrap()
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-    let r2: [BooleanBlock; 8] = (0..8)
-        .map(|i| {
-            var.lock()
-                .unwrap()
-                .get(&format!("y{}", i + 8))
-                .cloned()
-                .unwrap()
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-    let r3: [BooleanBlock; 8] = (0..8)
-        .map(|i| {
-            var.lock()
-                .unwrap()
-                .get(&format!("y{}", i + 16))
-                .cloned()
-                .unwrap()
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-    let r4: [BooleanBlock; 8] = (0..8)
-        .map(|i| {
-            var.lock()
-                .unwrap()
-                .get(&format!("y{}", i + 24))
-                .cloned()
-                .unwrap()
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-
-    ColBoolBlocks::new([r1, r2, r3, r4])
+    Ok(())
 }
 
+// The built-in circuits below are built from the constant instruction lists,
+// bool_circ_tests checks them on every input. Their widths match the fixed
+// size arrays of sbox_bc, sbox_inv_bc, mix_cols and inv_mix_cols, so the
+// expects above and here only fire if one of these constants is broken, never
+// on the caller's input.
+pub static SBOX_CIRCUIT: LazyLock<BoolCircuit> = LazyLock::new(|| {
+    BoolCircuit::from_instr(&SBOX_INSTR, &wire_names("x", 8), &wire_names("s", 8))
+        .expect("SBOX_INSTR")
+});
+
+pub static SBOX_INV_CIRCUIT: LazyLock<BoolCircuit> = LazyLock::new(|| {
+    BoolCircuit::from_instr(&SBOX_INV_INSTR, &wire_names("u", 8), &wire_names("s", 8))
+        .expect("SBOX_INV_INSTR")
+});
+
+pub static MIX_COLS_CIRCUIT: LazyLock<BoolCircuit> = LazyLock::new(|| {
+    BoolCircuit::from_instr(&MIX_COLS_INSTR, &wire_names("x", 32), &wire_names("y", 32))
+        .expect("MIX_COLS_INSTR")
+});
+
+// InvMixColumns = MixColumns . circulant(05, 00, 04, 00), XOR only
+pub static INV_MIX_COLS_CIRCUIT: LazyLock<BoolCircuit> = LazyLock::new(|| {
+    BoolCircuit::from_instr(
+        &INV_MIX_COLS_PRE_INSTR,
+        &wire_names("x", 32),
+        &wire_names("b", 32),
+    )
+    .and_then(|pre| pre.compose(&MIX_COLS_CIRCUIT))
+    .expect("INV_MIX_COLS_PRE_INSTR")
+});
+
 // https://eprint.iacr.org/2009/191.pdf
 const SBOX_INSTR: [&str; 119] = [
     "y14 = x3 ^ x5",
@@ -774,6 +572,135 @@ const SBOX_INSTR: [&str; 119] = [
     "s0 = t59 ^ t63",
 ];
 
+// inputs u0..u7 and outputs s0..s7 msb first, as in SBOX_INSTR
+const SBOX_INV_INSTR: [&str; 125] = [
+    "y0 = u0 ^ u3",
+    "y2n = u1 ^ u3",
+    "y2 = y2n !",
+    "y4 = u0 ^ y2",
+    "rtl0 = u6 ^ u7",
+    "y1 = y2 ^ rtl0",
+    "y7n = u2 ^ y1",
+    "y7 = y7n !",
+    "rtl1 = u3 ^ u4",
+    "y6n = u7 ^ rtl1",
+    "y6 = y6n !",
+    "y3 = y1 ^ rtl1",
+    "rtl2n = u0 ^ u2",
+    "rtl2 = rtl2n !",
+    "y5 = u5 ^ rtl2",
+    "sa1 = y0 ^ y2",
+    "sa0 = y1 ^ y3",
+    "sb1 = y4 ^ y6",
+    "sb0 = y5 ^ y7",
+    "ah = y0 ^ y1",
+    "al = y2 ^ y3",
+    "aa = sa0 ^ sa1",
+    "bh = y4 ^ y5",
+    "bl = y6 ^ y7",
+    "bb = sb0 ^ sb1",
+    "ab20 = sa0 ^ sb0",
+    "ab22 = al ^ bl",
+    "ab23 = y3 ^ y7",
+    "ab21 = sa1 ^ sb1",
+    "abcd1 = ah & bh",
+    "rr1 = y0 & y4",
+    "ph11 = ab20 ^ abcd1",
+    "t01 = y1 & y5",
+    "ph01 = t01 ^ abcd1",
+    "abcd2 = al & bl",
+    "r1 = y2 & y6",
+    "pl11 = ab22 ^ abcd2",
+    "r2 = y3 & y7",
+    "pl01 = r2 ^ abcd2",
+    "r3 = sa0 & sb0",
+    "vr1 = aa & bb",
+    "pr1 = vr1 ^ r3",
+    "wr1 = sa1 & sb1",
+    "qr1 = wr1 ^ r3",
+    "ab0 = ph11 ^ rr1",
+    "ab1 = ph01 ^ ab21",
+    "ab2 = pl11 ^ r1",
+    "ab3 = pl01 ^ qr1",
+    "cp1 = ab0 ^ pr1",
+    "cp2 = ab1 ^ qr1",
+    "cp3 = ab2 ^ pr1",
+    "cp4 = ab3 ^ ab23",
+    "tinv1 = cp3 ^ cp4",
+    "tinv2 = cp3 & cp1",
+    "tinv3 = cp2 ^ tinv2",
+    "tinv4 = cp1 ^ cp2",
+    "tinv5 = cp4 ^ tinv2",
+    "tinv6 = tinv5 & tinv4",
+    "tinv7 = tinv3 & tinv1",
+    "d2 = cp4 ^ tinv7",
+    "d0 = cp2 ^ tinv6",
+    "tinv8 = cp1 & cp4",
+    "tinv9 = tinv4 & tinv8",
+    "tinv10 = tinv4 ^ tinv2",
+    "d1 = tinv9 ^ tinv10",
+    "tinv11 = cp2 & cp3",
+    "tinv12 = tinv1 & tinv11",
+    "tinv13 = tinv1 ^ tinv2",
+    "d3 = tinv12 ^ tinv13",
+    "sd1 = d1 ^ d3",
+    "sd0 = d0 ^ d2",
+    "dl = d0 ^ d1",
+    "dh = d2 ^ d3",
+    "dd = sd0 ^ sd1",
+    "abcd3 = dh & bh",
+    "rr2 = d3 & y4",
+    "t02 = d2 & y5",
+    "abcd4 = dl & bl",
+    "r4 = d1 & y6",
+    "r5 = d0 & y7",
+    "r6 = sd0 & sb0",
+    "vr2 = dd & bb",
+    "wr2 = sd1 & sb1",
+    "abcd5 = dh & ah",
+    "r7 = d3 & y0",
+    "r8 = d2 & y1",
+    "abcd6 = dl & al",
+    "r9 = d1 & y2",
+    "r10 = d0 & y3",
+    "r11 = sd0 & sa0",
+    "vr3 = dd & aa",
+    "wr3 = sd1 & sa1",
+    "ph12 = rr2 ^ abcd3",
+    "ph02 = t02 ^ abcd3",
+    "pl12 = r4 ^ abcd4",
+    "pl02 = r5 ^ abcd4",
+    "pr2 = vr2 ^ r6",
+    "qr2 = wr2 ^ r6",
+    "p0 = ph12 ^ pr2",
+    "p1 = ph02 ^ qr2",
+    "p2 = pl12 ^ pr2",
+    "p3 = pl02 ^ qr2",
+    "ph13 = r7 ^ abcd5",
+    "ph03 = r8 ^ abcd5",
+    "pl13 = r9 ^ abcd6",
+    "pl03 = r10 ^ abcd6",
+    "pr3 = vr3 ^ r11",
+    "qr3 = wr3 ^ r11",
+    "p4 = ph13 ^ pr3",
+    "s7 = ph03 ^ qr3",
+    "p6 = pl13 ^ pr3",
+    "p7 = pl03 ^ qr3",
+    "s3 = p1 ^ p6",
+    "s6 = p2 ^ p6",
+    "s0 = p3 ^ p6",
+    "x11 = p0 ^ p2",
+    "s5 = s0 ^ x11",
+    "x13 = p4 ^ p7",
+    "x14 = x11 ^ x13",
+    "s1 = s3 ^ x14",
+    "x16 = p1 ^ s7",
+    "s2 = x14 ^ x16",
+    "x18 = p0 ^ p4",
+    "x19 = s5 ^ x16",
+    "s4 = x18 ^ x19",
+];
+
 // https://eprint.iacr.org/2019/833.pdf
 const MIX_COLS_INSTR: [&str; 92] = [
     "t0 = x0 ^ x8",
@@ -869,3 +796,66 @@ const MIX_COLS_INSTR: [&str; 92] = [
     "t59 = t17 ^ t58",
     "y28 = x20 ^ t59",
 ];
+
+// b_i = 05 * x_i ^ 04 * x_(i + 2) = x_i ^ 04 * t, with t = x_i ^ x_(i + 2)
+// shared by the rows i and i + 2, z = 04 * t
+const INV_MIX_COLS_PRE_INSTR: [&str; 58] = [
+    "t0 = x0 ^ x16",
+    "t1 = x1 ^ x17",
+    "t2 = x2 ^ x18",
+    "t3 = x3 ^ x19",
+    "t4 = x4 ^ x20",
+    "t5 = x5 ^ x21",
+    "t6 = x6 ^ x22",
+    "t7 = x7 ^ x23",
+    "z1 = t7 ^ t6",
+    "z2 = t0 ^ t7",
+    "z3 = t1 ^ t6",
+    "z4 = t2 ^ z1",
+    "z5 = t3 ^ t7",
+    "b0 = x0 ^ t6",
+    "b16 = x16 ^ t6",
+    "b1 = x1 ^ z1",
+    "b17 = x17 ^ z1",
+    "b2 = x2 ^ z2",
+    "b18 = x18 ^ z2",
+    "b3 = x3 ^ z3",
+    "b19 = x19 ^ z3",
+    "b4 = x4 ^ z4",
+    "b20 = x20 ^ z4",
+    "b5 = x5 ^ z5",
+    "b21 = x21 ^ z5",
+    "b6 = x6 ^ t4",
+    "b22 = x22 ^ t4",
+    "b7 = x7 ^ t5",
+    "b23 = x23 ^ t5",
+    "t8 = x8 ^ x24",
+    "t9 = x9 ^ x25",
+    "t10 = x10 ^ x26",
+    "t11 = x11 ^ x27",
+    "t12 = x12 ^ x28",
+    "t13 = x13 ^ x29",
+    "t14 = x14 ^ x30",
+    "t15 = x15 ^ x31",
+    "z9 = t15 ^ t14",
+    "z10 = t8 ^ t15",
+    "z11 = t9 ^ t14",
+    "z12 = t10 ^ z9",
+    "z13 = t11 ^ t15",
+    "b8 = x8 ^ t14",
+    "b24 = x24 ^ t14",
+    "b9 = x9 ^ z9",
+    "b25 = x25 ^ z9",
+    "b10 = x10 ^ z10",
+    "b26 = x26 ^ z10",
+    "b11 = x11 ^ z11",
+    "b27 = x27 ^ z11",
+    "b12 = x12 ^ z12",
+    "b28 = x28 ^ z12",
+    "b13 = x13 ^ z13",
+    "b29 = x29 ^ z13",
+    "b14 = x14 ^ t12",
+    "b30 = x30 ^ t12",
+    "b15 = x15 ^ t13",
+    "b31 = x31 ^ t13",
+];
@@ -1,5 +1,7 @@
 use crate::aes128_keyschedule::{BLOCKSIZE, KEYSIZE, key_expansion};
 use crate::aes128_rdx_fhe::{decrypt_block_fhe, encrypt_block_fhe};
+use crate::error::AesFheError;
+use crate::metrics::{Metrics, trace_elapsed};
 
 use std::time::Instant;
 
@@ -7,33 +9,31 @@ pub fn encrypt_block_iter_fhe(
     input: &[u8; BLOCKSIZE],
     key: &[u8; KEYSIZE],
     iter: usize,
-) -> [u8; BLOCKSIZE] {
+) -> Result<([u8; BLOCKSIZE], Metrics), AesFheError> {
     let mut out_iter = [0u8; BLOCKSIZE];
 
+    // the key is expanded in the clear, outside the FHE runs
     let start = Instant::now();
     let xk = key_expansion(key);
-    let key_expansion_elapsed = start.elapsed();
-    println!("AES key expansion took: {key_expansion_elapsed:?}");
+    trace_elapsed("key_expansion", start);
 
-    encrypt_block_fhe(input, &xk, &mut out_iter, iter);
-    println!("cipher encrypt {:?}", out_iter);
+    let metrics = encrypt_block_fhe(input, &xk, &mut out_iter, iter)?;
 
-    out_iter
+    Ok((out_iter, metrics))
 }
 
 pub fn decrypt_block_iter_fhe(
     input: &[u8; BLOCKSIZE],
     key: &[u8; KEYSIZE],
     iter: usize,
-) -> [u8; BLOCKSIZE] {
+) -> Result<([u8; BLOCKSIZE], Metrics), AesFheError> {
     let mut out_iter = [0u8; BLOCKSIZE];
 
     let start = Instant::now();
     let xk = key_expansion(key);
-    let key_expansion_elapsed = start.elapsed();
-    println!("AES key expansion took: {key_expansion_elapsed:?}");
+    trace_elapsed("key_expansion", start);
 
-    decrypt_block_fhe(input, &xk, &mut out_iter, iter);
+    let metrics = decrypt_block_fhe(input, &xk, &mut out_iter, iter)?;
 
-    out_iter
+    Ok((out_iter, metrics))
 }
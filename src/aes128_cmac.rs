@@ -0,0 +1,144 @@
+use crate::aes_fhe::num_blocks;
+use crate::aes128_bool_circ::{PosVals, get_bits_from_block, get_block_from_bits};
+use crate::aes128_keyschedule::BLOCKSIZE;
+use crate::aes128_rdx_fhe::{add_round_key_fhe, encrypt_state_with_fhe};
+use crate::aes128_sbox::{SboxBackend, SboxCircuit};
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
+
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::integer::{BooleanBlock, ServerKey};
+use tfhe::shortint::Ciphertext;
+
+use rayon::prelude::*;
+
+// x^128 + x^7 + x^2 + x + 1
+const R_128: [usize; 4] = [120, 125, 126, 127];
+
+// multiply by x in GF(2^128), msb first bits
+pub fn cmac_double_bc(bits: &[BooleanBlock], sk: &ServerKey) -> Vec<BooleanBlock> {
+    assert!(bits.len() == 128);
+
+    let msb = &bits[0];
+    let mut out: Vec<BooleanBlock> = bits[1..].to_vec();
+    out.push(sk.create_trivial_boolean_block(false));
+
+    out.par_iter_mut().enumerate().for_each(|(i, bit)| {
+        if R_128.contains(&i) {
+            *bit = sk.boolean_bitxor(bit, msb);
+        }
+    });
+
+    out
+}
+
+fn cmac_l_bits(
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    pos_vals: &PosVals,
+    m: &mut Metrics,
+    sk: &ServerKey,
+) -> Result<Vec<BooleanBlock>, AesFheError> {
+    let zero = EncryptedState::trivial(&[0u8; BLOCKSIZE], sk);
+    let (l_ck, l_metrics) = encrypt_state_with_fhe(&zero, xk, sbox, sk)?;
+    m.nest(&l_metrics);
+
+    Ok(m.time("to_bits", || get_bits_from_block(&l_ck, pos_vals, sk)))
+}
+
+// K1 and K2
+pub type Subkeys = (EncryptedState, EncryptedState);
+
+// RFC 4493 2.3
+pub fn cmac_subkeys_fhe(xk: &EncryptedKeySchedule, sk: &ServerKey) -> Result<Subkeys, AesFheError> {
+    Ok(cmac_subkeys_with_fhe(xk, &SboxCircuit, sk)?.0)
+}
+
+pub fn cmac_subkeys_with_fhe(
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Subkeys, Metrics), AesFheError> {
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut metrics = Metrics::new();
+
+    let subkeys = metrics.run(|m| {
+        let l_bits = cmac_l_bits(xk, sbox, &pos_vals, m, sk)?;
+        let k1_bits = m.time("double", || cmac_double_bc(&l_bits, sk));
+        let k2_bits = m.time("double", || cmac_double_bc(&k1_bits, sk));
+
+        m.time("from_bits", || {
+            Ok::<_, AesFheError>((
+                EncryptedState::new(get_block_from_bits(&k1_bits, &pos_vals, sk)?)?,
+                EncryptedState::new(get_block_from_bits(&k2_bits, &pos_vals, sk)?)?,
+            ))
+        })
+    })?;
+
+    Ok((subkeys, metrics))
+}
+
+// RFC 4493 2.4, the message length is public
+pub fn cmac_fhe(
+    xk: &EncryptedKeySchedule,
+    msg: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<EncryptedState, AesFheError> {
+    Ok(cmac_with_fhe(xk, msg, &SboxCircuit, sk)?.0)
+}
+
+pub fn cmac_with_fhe(
+    xk: &EncryptedKeySchedule,
+    msg: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(EncryptedState, Metrics), AesFheError> {
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut metrics = Metrics::new();
+
+    let n_blocks = msg.len().div_ceil(BLOCKSIZE).max(1);
+    let complete = !msg.is_empty() && msg.len().is_multiple_of(BLOCKSIZE);
+
+    let tag = metrics.run(|m| {
+        // only the subkey for the last block is converted back
+        let l_bits = cmac_l_bits(xk, sbox, &pos_vals, m, sk)?;
+        let k1_bits = m.time("double", || cmac_double_bc(&l_bits, sk));
+        let subkey = match complete {
+            true => m.time("from_bits", || get_block_from_bits(&k1_bits, &pos_vals, sk))?,
+            false => {
+                let k2_bits = m.time("double", || cmac_double_bc(&k1_bits, sk));
+                m.time("from_bits", || get_block_from_bits(&k2_bits, &pos_vals, sk))?
+            }
+        };
+
+        let mut last = msg[(n_blocks - 1) * BLOCKSIZE..].to_vec();
+        if !complete {
+            last.push(sk.create_trivial_radix(0x80u8, num_blocks(sk)));
+            last.resize_with(BLOCKSIZE, || sk.create_trivial_radix(0u8, num_blocks(sk)));
+        }
+        let mut last = EncryptedState::new(last)?;
+        m.time("chain_xor", || add_round_key_fhe(&mut last, &subkey, sk))?;
+
+        let mut x: Option<EncryptedState> = None;
+        for block in msg.chunks(BLOCKSIZE).take(n_blocks - 1) {
+            let mut y = EncryptedState::new(block.to_vec())?;
+            if let Some(x) = &x {
+                m.time("chain_xor", || add_round_key_fhe(&mut y, x, sk))?;
+            }
+            let (next, block_metrics) = encrypt_state_with_fhe(&y, xk, sbox, sk)?;
+            m.nest(&block_metrics);
+            x = Some(next);
+        }
+
+        if let Some(x) = &x {
+            m.time("chain_xor", || add_round_key_fhe(&mut last, x, sk))?;
+        }
+        let (tag, block_metrics) = encrypt_state_with_fhe(&last, xk, sbox, sk)?;
+        m.nest(&block_metrics);
+
+        Ok::<_, AesFheError>(tag)
+    })?;
+
+    Ok((tag, metrics))
+}
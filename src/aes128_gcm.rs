@@ -0,0 +1,308 @@
+use crate::aes128_bool_circ::{PosVals, get_bits_from_block, get_block_from_bits};
+use crate::aes128_keyschedule::BLOCKSIZE;
+use crate::aes128_modes::counter_keystream_with_fhe;
+use crate::aes128_sbox::{SboxBackend, SboxCircuit};
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
+
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::integer::{BooleanBlock, ServerKey};
+use tfhe::shortint::Ciphertext;
+
+use rayon::prelude::*;
+
+pub const IVSIZE: usize = 12;
+
+// R = 11100001 || 0^120, bit 0 is the msb of byte 0
+const R_GCM: [usize; 4] = [0, 1, 2, 7];
+
+// SP 800-38D 6.2 inc_32
+#[inline]
+pub fn gcm_counter_block(j0: &[u8; BLOCKSIZE], i: u32) -> [u8; BLOCKSIZE] {
+    let mut ctr = *j0;
+    let lo = u32::from_be_bytes([j0[12], j0[13], j0[14], j0[15]]).wrapping_add(i);
+    ctr[12..].copy_from_slice(&lo.to_be_bytes());
+
+    ctr
+}
+
+fn gcm_j0(iv: &[u8; IVSIZE]) -> [u8; BLOCKSIZE] {
+    let mut j0 = [0u8; BLOCKSIZE];
+    j0[..IVSIZE].copy_from_slice(iv);
+    j0[BLOCKSIZE - 1] = 1;
+
+    j0
+}
+
+// zero padded 128 bit blocks of public data as trivial BooleanBlocks
+fn plain_bits(data: &[u8], sk: &ServerKey) -> Vec<Vec<BooleanBlock>> {
+    data.chunks(BLOCKSIZE)
+        .map(|chunk| {
+            (0..128)
+                .map(|i| {
+                    let byte = chunk.get(i / 8).copied().unwrap_or(0);
+                    sk.create_trivial_boolean_block((byte >> (7 - i % 8)) & 1 == 1)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn len_bits(aad_len: usize, ct_len: usize, sk: &ServerKey) -> Vec<BooleanBlock> {
+    let mut len_block = [0u8; BLOCKSIZE];
+    len_block[..8].copy_from_slice(&(8 * aad_len as u64).to_be_bytes());
+    len_block[8..].copy_from_slice(&(8 * ct_len as u64).to_be_bytes());
+
+    plain_bits(&len_block, sk).remove(0)
+}
+
+// h_table[i] = H * x^i, the reduction is folded into the table so a product is AND and XOR only
+pub fn ghash_table_bc(h: &[BooleanBlock], sk: &ServerKey) -> Vec<Vec<BooleanBlock>> {
+    assert!(h.len() == 128);
+
+    let mut h_table: Vec<Vec<BooleanBlock>> = Vec::with_capacity(128);
+    h_table.push(h.to_vec());
+
+    for i in 1..128 {
+        let prev = &h_table[i - 1];
+        let lsb = &prev[127];
+
+        let mut v = Vec::with_capacity(128);
+        v.push(sk.create_trivial_boolean_block(false));
+        v.extend_from_slice(&prev[..127]);
+
+        v.par_iter_mut().enumerate().for_each(|(j, bit)| {
+            if R_GCM.contains(&j) {
+                *bit = sk.boolean_bitxor(bit, lsb);
+            }
+        });
+
+        h_table.push(v);
+    }
+
+    h_table
+}
+
+// carry-less multiplication in GF(2^128), SP 800-38D 6.3
+pub fn gf128_mul_bc(
+    x: &[BooleanBlock],
+    h_table: &[Vec<BooleanBlock>],
+    sk: &ServerKey,
+) -> Vec<BooleanBlock> {
+    assert!(x.len() == 128);
+    assert!(h_table.len() == 128);
+
+    (0..128)
+        .into_par_iter()
+        .map(|j| {
+            (0..128)
+                .into_par_iter()
+                .map(|i| sk.boolean_bitand(&x[i], &h_table[i][j]))
+                .reduce_with(|a, b| sk.boolean_bitxor(&a, &b))
+                .unwrap()
+        })
+        .collect()
+}
+
+// SP 800-38D 6.4
+pub fn ghash_bc(
+    blocks: &[Vec<BooleanBlock>],
+    h_table: &[Vec<BooleanBlock>],
+    sk: &ServerKey,
+) -> Vec<BooleanBlock> {
+    let mut y: Option<Vec<BooleanBlock>> = None;
+    for x in blocks {
+        let y_x: Vec<BooleanBlock> = match &y {
+            Some(y) => y
+                .par_iter()
+                .zip(x.par_iter())
+                .map(|(a, b)| sk.boolean_bitxor(a, b))
+                .collect(),
+            None => x.clone(),
+        };
+        y = Some(gf128_mul_bc(&y_x, h_table, sk));
+    }
+
+    y.unwrap_or_else(|| {
+        (0..128)
+            .map(|_| sk.create_trivial_boolean_block(false))
+            .collect()
+    })
+}
+
+struct GcmKeystream {
+    h: EncryptedState,
+    ek_j0: EncryptedState,
+    keystream: Vec<BaseRadixCiphertext<Ciphertext>>,
+}
+
+// H, E(K, J0) and the CTR keystream in one parallel batch
+fn gcm_keystream_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &[u8; IVSIZE],
+    len: usize,
+    sbox: &dyn SboxBackend,
+    m: &mut Metrics,
+    sk: &ServerKey,
+) -> Result<GcmKeystream, AesFheError> {
+    let j0 = gcm_j0(iv);
+    let n_blocks = len.div_ceil(BLOCKSIZE);
+
+    let mut counters = vec![[0u8; BLOCKSIZE], j0];
+    counters.extend((1..=n_blocks as u32).map(|i| gcm_counter_block(&j0, i)));
+
+    let (keystream, ks_metrics) = counter_keystream_with_fhe(xk, &counters, sbox, sk)?;
+    m.nest(&ks_metrics);
+
+    let mut keystream = keystream.into_iter();
+    let h = keystream.next().unwrap();
+    let ek_j0 = keystream.next().unwrap();
+    let keystream: Vec<BaseRadixCiphertext<Ciphertext>> = keystream
+        .flat_map(EncryptedState::into_vec)
+        .take(len)
+        .collect();
+
+    Ok(GcmKeystream {
+        h,
+        ek_j0,
+        keystream,
+    })
+}
+
+fn gcm_tag_fhe(
+    ghash_in: &[Vec<BooleanBlock>],
+    gcm_ks: &GcmKeystream,
+    pos_vals: &PosVals,
+    m: &mut Metrics,
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    let h_bits = m.time("to_bits", || get_bits_from_block(&gcm_ks.h, pos_vals, sk));
+    let h_table = m.time("ghash_table", || ghash_table_bc(&h_bits, sk));
+    let s_bits = m.time("ghash", || ghash_bc(ghash_in, &h_table, sk));
+    let s = m.time("from_bits", || get_block_from_bits(&s_bits, pos_vals, sk))?;
+
+    Ok(m.time("tag_xor", || {
+        s.par_iter()
+            .zip(gcm_ks.ek_j0.par_iter())
+            .map(|(a, b)| sk.unchecked_bitxor(a, b))
+            .collect()
+    }))
+}
+
+// the ciphertext and the tag, both encrypted
+pub type CiphertextTag = (
+    Vec<BaseRadixCiphertext<Ciphertext>>,
+    Vec<BaseRadixCiphertext<Ciphertext>>,
+);
+
+// encrypted plaintext in, encrypted AES-GCM ciphertext and tag out
+pub fn gcm_encrypt_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &[u8; IVSIZE],
+    aad: &[u8],
+    pt: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<CiphertextTag, AesFheError> {
+    Ok(gcm_encrypt_with_fhe(xk, iv, aad, pt, &SboxCircuit, sk)?.0)
+}
+
+pub fn gcm_encrypt_with_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &[u8; IVSIZE],
+    aad: &[u8],
+    pt: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(CiphertextTag, Metrics), AesFheError> {
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let gcm_ks = gcm_keystream_fhe(xk, iv, pt.len(), sbox, m, sk)?;
+
+        let ct: Vec<BaseRadixCiphertext<Ciphertext>> = m.time("keystream_xor", || {
+            gcm_ks
+                .keystream
+                .par_iter()
+                .zip(pt.par_iter())
+                .map(|(ks, p)| sk.unchecked_bitxor(ks, p))
+                .collect()
+        });
+
+        let mut ghash_in = plain_bits(aad, sk);
+        let zero = sk.create_trivial_boolean_block(false);
+        for block in ct.chunks(BLOCKSIZE) {
+            let mut bits = m.time("to_bits", || get_bits_from_block(block, &pos_vals, sk));
+            bits.resize(128, zero.clone());
+            ghash_in.push(bits);
+        }
+        ghash_in.push(len_bits(aad.len(), ct.len(), sk));
+
+        let tag = gcm_tag_fhe(&ghash_in, &gcm_ks, &pos_vals, m, sk)?;
+
+        Ok::<_, AesFheError>((ct, tag))
+    })?;
+
+    Ok((out, metrics))
+}
+
+// the plaintext and whether the tag matched, both encrypted
+pub type PlaintextValid = (Vec<BaseRadixCiphertext<Ciphertext>>, BooleanBlock);
+
+// AES-GCM ciphertext and tag in, FHE ciphertext of the plaintext and an encrypted tag check out
+pub fn gcm_transcipher_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &[u8; IVSIZE],
+    aad: &[u8],
+    ct: &[u8],
+    tag: &[u8; BLOCKSIZE],
+    sk: &ServerKey,
+) -> Result<PlaintextValid, AesFheError> {
+    Ok(gcm_transcipher_with_fhe(xk, iv, aad, ct, tag, &SboxCircuit, sk)?.0)
+}
+
+pub fn gcm_transcipher_with_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &[u8; IVSIZE],
+    aad: &[u8],
+    ct: &[u8],
+    tag: &[u8; BLOCKSIZE],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(PlaintextValid, Metrics), AesFheError> {
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let gcm_ks = gcm_keystream_fhe(xk, iv, ct.len(), sbox, m, sk)?;
+
+        let pt: Vec<BaseRadixCiphertext<Ciphertext>> = m.time("keystream_xor", || {
+            gcm_ks
+                .keystream
+                .par_iter()
+                .zip(ct.par_iter())
+                .map(|(ks, &byte)| sk.unchecked_scalar_bitxor_parallelized(ks, byte))
+                .collect()
+        });
+
+        let mut ghash_in = plain_bits(aad, sk);
+        ghash_in.extend(plain_bits(ct, sk));
+        ghash_in.push(len_bits(aad.len(), ct.len(), sk));
+
+        let tag_ck = gcm_tag_fhe(&ghash_in, &gcm_ks, &pos_vals, m, sk)?;
+
+        let valid = m.time("tag_check", || {
+            tag_ck
+                .par_iter()
+                .zip(tag.par_iter())
+                .map(|(t, &byte)| sk.scalar_eq_parallelized(t, byte))
+                .reduce_with(|a, b| sk.boolean_bitand(&a, &b))
+                .unwrap()
+        });
+
+        Ok::<_, AesFheError>((pt, valid))
+    })?;
+
+    Ok((out, metrics))
+}
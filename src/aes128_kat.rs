@@ -0,0 +1,443 @@
+use crate::aes_fhe::{enc_round_keys_fhe, enc_state_fhe, hex, parse_hex};
+use crate::aes128_keyschedule::{BLOCKSIZE, KeySize, expand_key};
+use crate::aes128_rdx_fhe::{decrypt_state_fhe, encrypt_state_fhe};
+use crate::aes128_ref::{decrypt_state, encrypt_state};
+use crate::error::AesFheError;
+
+use tfhe::integer::{RadixClientKey, ServerKey};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::index;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// NIST CAVP known answer tests (AESAVS), the ECB response files
+// ECB{GFSbox,KeySbox,VarKey,VarTxt,MCT}{128,192,256}.rsp
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KatKind {
+    GfSbox,
+    KeySbox,
+    VarKey,
+    VarTxt,
+    // Monte Carlo, every record chains 1000 blocks under its key
+    Mct,
+}
+
+impl KatKind {
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("ECB")?;
+        [
+            ("GFSbox", KatKind::GfSbox),
+            ("KeySbox", KatKind::KeySbox),
+            ("VarKey", KatKind::VarKey),
+            ("VarTxt", KatKind::VarTxt),
+            ("MCT", KatKind::Mct),
+        ]
+        .into_iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, kind)| kind)
+    }
+
+    // blocks chained through the cipher from the input to the expected output
+    #[inline]
+    pub const fn iterations(self) -> usize {
+        match self {
+            KatKind::Mct => 1000,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KatDirection {
+    Encrypt,
+    Decrypt,
+}
+
+impl fmt::Display for KatDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KatDirection::Encrypt => write!(f, "ENCRYPT"),
+            KatDirection::Decrypt => write!(f, "DECRYPT"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KatVector {
+    pub file: String,
+    pub kind: KatKind,
+    pub direction: KatDirection,
+    pub count: usize,
+    pub key: Vec<u8>,
+    pub plaintext: [u8; BLOCKSIZE],
+    pub ciphertext: [u8; BLOCKSIZE],
+}
+
+impl KatVector {
+    #[inline]
+    pub fn input(&self) -> &[u8; BLOCKSIZE] {
+        match self.direction {
+            KatDirection::Encrypt => &self.plaintext,
+            KatDirection::Decrypt => &self.ciphertext,
+        }
+    }
+
+    #[inline]
+    pub fn expected(&self) -> &[u8; BLOCKSIZE] {
+        match self.direction {
+            KatDirection::Encrypt => &self.ciphertext,
+            KatDirection::Decrypt => &self.plaintext,
+        }
+    }
+}
+
+impl fmt::Display for KatVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} COUNT = {}", self.file, self.direction, self.count)
+    }
+}
+
+#[derive(Default)]
+struct Record {
+    count: Option<usize>,
+    key: Option<Vec<u8>>,
+    plaintext: Option<[u8; BLOCKSIZE]>,
+    ciphertext: Option<[u8; BLOCKSIZE]>,
+}
+
+impl Record {
+    fn is_empty(&self) -> bool {
+        self.count.is_none()
+            && self.key.is_none()
+            && self.plaintext.is_none()
+            && self.ciphertext.is_none()
+    }
+}
+
+// "KEY = 00..", "[ENCRYPT]" and "# comment" lines, fields other than COUNT,
+// KEY, PLAINTEXT and CIPHERTEXT are ignored
+pub fn parse_rsp(file: &str, kind: KatKind, text: &str) -> Result<Vec<KatVector>, AesFheError> {
+    let mut vectors = Vec::new();
+    let mut direction = None;
+    let mut record = Record::default();
+
+    let mut flush = |record: &mut Record, direction: Option<KatDirection>| {
+        if record.is_empty() {
+            return Ok(());
+        }
+        let record = std::mem::take(record);
+        let missing = |field: &str| {
+            AesFheError::MalformedVector(format!("{}: record without {}", file, field))
+        };
+
+        let key = record.key.ok_or_else(|| missing("KEY"))?;
+        KeySize::from_key_len(key.len()).ok_or(AesFheError::KeyLength(key.len()))?;
+
+        vectors.push(KatVector {
+            file: file.to_string(),
+            kind,
+            direction: direction.ok_or_else(|| missing("[ENCRYPT] or [DECRYPT]"))?,
+            count: record.count.ok_or_else(|| missing("COUNT"))?,
+            key,
+            plaintext: record.plaintext.ok_or_else(|| missing("PLAINTEXT"))?,
+            ciphertext: record.ciphertext.ok_or_else(|| missing("CIPHERTEXT"))?,
+        });
+
+        Ok::<(), AesFheError>(())
+    };
+
+    for line in text.lines().map(str::trim) {
+        let malformed = || AesFheError::MalformedVector(format!("{}: {}", file, line));
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            flush(&mut record, direction)?;
+            direction = match section {
+                "ENCRYPT" => Some(KatDirection::Encrypt),
+                "DECRYPT" => Some(KatDirection::Decrypt),
+                _ => return Err(malformed()),
+            };
+            continue;
+        }
+
+        let (field, value) = line.split_once('=').ok_or_else(malformed)?;
+        let (field, value) = (field.trim(), value.trim());
+        let block = || -> Result<[u8; BLOCKSIZE], AesFheError> {
+            parse_hex(value)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(malformed)
+        };
+
+        match field {
+            "COUNT" => {
+                flush(&mut record, direction)?;
+                record.count = Some(value.parse().map_err(|_| malformed())?);
+            }
+            "KEY" => record.key = Some(parse_hex(value).ok_or_else(malformed)?),
+            "PLAINTEXT" => record.plaintext = Some(block()?),
+            "CIPHERTEXT" => record.ciphertext = Some(block()?),
+            _ => {}
+        }
+    }
+    flush(&mut record, direction)?;
+
+    Ok(vectors)
+}
+
+pub fn load_rsp(path: &Path) -> Result<Vec<KatVector>, AesFheError> {
+    let file = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let kind = KatKind::from_file_name(file).ok_or_else(|| {
+        AesFheError::MalformedVector(format!("{}: not an ECB known answer file", file))
+    })?;
+
+    let text = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+
+    parse_rsp(file, kind, &text)
+}
+
+// every ECB*.rsp file of the directory in name order, the other modes are skipped
+pub fn load_rsp_dir(dir: &Path) -> Result<Vec<KatVector>, AesFheError> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+
+    let mut vectors = Vec::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if name.ends_with(".rsp") && KatKind::from_file_name(name).is_some() {
+            vectors.extend(load_rsp(&path)?);
+        }
+    }
+
+    Ok(vectors)
+}
+
+// every vector of the KAT files, without the Monte Carlo ones
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KatOptions {
+    // vectors per file and direction, None runs them all
+    pub sample: Option<usize>,
+    // the same seed picks the same vectors
+    pub seed: u64,
+    // MCT records cost 1000 blocks each, off unless asked for
+    pub mct: bool,
+}
+
+// a random subset of every file and direction, kept in file order
+pub fn sample_vectors<'a>(vectors: &'a [KatVector], options: &KatOptions) -> Vec<&'a KatVector> {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut sampled = Vec::new();
+
+    let mut start = 0;
+    while start < vectors.len() {
+        let first = &vectors[start];
+        let len = vectors[start..]
+            .iter()
+            .take_while(|v| v.file == first.file && v.direction == first.direction)
+            .count();
+        let vecs = &vectors[start..start + len];
+        start += len;
+
+        if vecs[0].kind == KatKind::Mct && !options.mct {
+            continue;
+        }
+        match options.sample {
+            Some(n) if n < len => {
+                let mut picked = index::sample(&mut rng, len, n).into_vec();
+                picked.sort_unstable();
+                sampled.extend(picked.into_iter().map(|i| &vecs[i]));
+            }
+            _ => sampled.extend(vecs),
+        }
+    }
+
+    sampled
+}
+
+// a cipher to run the vectors through, the block is chained `iterations` times
+pub trait KatCipher {
+    fn run(
+        &self,
+        direction: KatDirection,
+        key: &[u8],
+        input: &[u8; BLOCKSIZE],
+        iterations: usize,
+    ) -> Result<[u8; BLOCKSIZE], AesFheError>;
+}
+
+// the plaintext cipher of aes128_ref
+pub struct RefCipher;
+
+impl KatCipher for RefCipher {
+    fn run(
+        &self,
+        direction: KatDirection,
+        key: &[u8],
+        input: &[u8; BLOCKSIZE],
+        iterations: usize,
+    ) -> Result<[u8; BLOCKSIZE], AesFheError> {
+        let key_size = KeySize::from_key_len(key.len()).ok_or(AesFheError::KeyLength(key.len()))?;
+        let xk = expand_key(key, key_size)?;
+
+        let mut state = *input;
+        for _ in 0..iterations {
+            state = match direction {
+                KatDirection::Encrypt => encrypt_state(&state, &xk),
+                KatDirection::Decrypt => decrypt_state(&state, &xk),
+            };
+        }
+
+        Ok(state)
+    }
+}
+
+// client and server in one: the key is expanded and encrypted by the client,
+// the state stays encrypted from the first to the last iteration
+pub struct FheCipher<'a> {
+    ck: &'a RadixClientKey,
+    sk: &'a ServerKey,
+}
+
+impl<'a> FheCipher<'a> {
+    pub fn new(ck: &'a RadixClientKey, sk: &'a ServerKey) -> Self {
+        FheCipher { ck, sk }
+    }
+}
+
+impl KatCipher for FheCipher<'_> {
+    fn run(
+        &self,
+        direction: KatDirection,
+        key: &[u8],
+        input: &[u8; BLOCKSIZE],
+        iterations: usize,
+    ) -> Result<[u8; BLOCKSIZE], AesFheError> {
+        let key_size = KeySize::from_key_len(key.len()).ok_or(AesFheError::KeyLength(key.len()))?;
+        let xk_ck = enc_round_keys_fhe(&expand_key(key, key_size)?, self.ck)?;
+
+        let mut state_ck = enc_state_fhe(input, self.ck);
+        for _ in 0..iterations {
+            state_ck = match direction {
+                KatDirection::Encrypt => encrypt_state_fhe(&state_ck, &xk_ck, self.sk)?,
+                KatDirection::Decrypt => decrypt_state_fhe(&state_ck, &xk_ck, self.sk)?,
+            };
+        }
+
+        Ok(state_ck.decrypt(self.ck))
+    }
+}
+
+pub struct KatResult {
+    pub vector: KatVector,
+    pub output: Result<[u8; BLOCKSIZE], AesFheError>,
+    pub elapsed: Duration,
+}
+
+impl KatResult {
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.output
+            .as_ref()
+            .is_ok_and(|out| out == self.vector.expected())
+    }
+}
+
+impl fmt::Display for KatResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.passed() { "pass" } else { "FAIL" };
+        write!(f, "{} {} ({:.2?})", status, self.vector, self.elapsed)?;
+
+        match &self.output {
+            Ok(out) if !self.passed() => write!(
+                f,
+                ": got {}, expected {}",
+                hex(out),
+                hex(self.vector.expected())
+            ),
+            Err(e) => write!(f, ": {}", e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct KatReport {
+    pub results: Vec<KatResult>,
+}
+
+impl KatReport {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed()).count()
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &KatResult> {
+        self.results.iter().filter(|r| !r.passed())
+    }
+
+    #[inline]
+    pub fn all_passed(&self) -> bool {
+        self.failed().next().is_none()
+    }
+}
+
+// only the failures, the observer of run_kat sees every result
+impl fmt::Display for KatReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in self.failed() {
+            writeln!(f, "{}", result)?;
+        }
+        write!(
+            f,
+            "{} of {} vectors passed",
+            self.passed(),
+            self.results.len()
+        )
+    }
+}
+
+// one vector after the other, observe gets each result as soon as it is known
+pub fn run_kat(
+    vectors: &[&KatVector],
+    cipher: &dyn KatCipher,
+    mut observe: impl FnMut(&KatResult),
+) -> KatReport {
+    let mut report = KatReport::default();
+
+    for &vector in vectors {
+        let start = Instant::now();
+        let output = cipher.run(
+            vector.direction,
+            &vector.key,
+            vector.input(),
+            vector.kind.iterations(),
+        );
+
+        let result = KatResult {
+            vector: vector.clone(),
+            output,
+            elapsed: start.elapsed(),
+        };
+        observe(&result);
+        report.results.push(result);
+    }
+
+    report
+}
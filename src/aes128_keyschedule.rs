@@ -1,4 +1,16 @@
+use crate::aes_fhe::{check_params, num_blocks};
+use crate::aes128_bool_circ::PosVals;
+use crate::aes128_sbox::{SboxBackend, SboxCircuit};
+use crate::aes128_state::EncryptedKeySchedule;
 use crate::aes128_tables::SBOX;
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
+
+use tfhe::integer::ServerKey;
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::shortint::Ciphertext;
+
+use rayon::prelude::*;
 
 pub const KEYSIZE: usize = 16;
 pub const BLOCKSIZE: usize = 16;
@@ -9,29 +21,164 @@ const RCON: [u8; 11] = [
     0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1B, 0x36,
 ];
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeySize {
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl KeySize {
+    pub fn from_key_len(len: usize) -> Option<Self> {
+        match len {
+            16 => Some(KeySize::Aes128),
+            24 => Some(KeySize::Aes192),
+            32 => Some(KeySize::Aes256),
+            _ => None,
+        }
+    }
+
+    pub fn from_round_key_size(len: usize) -> Option<Self> {
+        [KeySize::Aes128, KeySize::Aes192, KeySize::Aes256]
+            .into_iter()
+            .find(|ks| ks.round_key_size() == len)
+    }
+
+    // Nk * 4
+    #[inline]
+    pub const fn key_len(self) -> usize {
+        match self {
+            KeySize::Aes128 => 16,
+            KeySize::Aes192 => 24,
+            KeySize::Aes256 => 32,
+        }
+    }
+
+    // Nr
+    #[inline]
+    pub const fn rounds(self) -> usize {
+        match self {
+            KeySize::Aes128 => 10,
+            KeySize::Aes192 => 12,
+            KeySize::Aes256 => 14,
+        }
+    }
+
+    #[inline]
+    pub const fn round_key_size(self) -> usize {
+        BLOCKSIZE * (self.rounds() + 1)
+    }
+}
+
 pub fn key_expansion(key: &[u8; 16]) -> [u8; 16 * 11] {
     let mut xk = [0u8; ROUNDKEYSIZE];
-    xk[0..KEYSIZE].copy_from_slice(key);
+    xk.copy_from_slice(&expand(key, KeySize::Aes128));
+
+    xk
+}
+
+pub fn expand_key(key: &[u8], key_size: KeySize) -> Result<Vec<u8>, AesFheError> {
+    if key.len() != key_size.key_len() {
+        return Err(AesFheError::KeyLength(key.len()));
+    }
+
+    Ok(expand(key, key_size))
+}
 
-    let mut i = KEYSIZE;
+// key is key_size.key_len() bytes
+fn expand(key: &[u8], key_size: KeySize) -> Vec<u8> {
+    let nk = key_size.key_len();
+    let xk_len = key_size.round_key_size();
+
+    let mut xk = vec![0u8; xk_len];
+    xk[0..nk].copy_from_slice(key);
+
+    let mut i = nk;
     let mut tmp = [0u8; 4];
 
-    while i < ROUNDKEYSIZE {
+    while i < xk_len {
         tmp.copy_from_slice(&xk[i - 4..i]);
 
-        if i % KEYSIZE == 0 {
+        if i.is_multiple_of(nk) {
             tmp.rotate_left(1);
             for j in 0..4 {
                 tmp[j] = SBOX[tmp[j] as usize];
             }
-            tmp[0] ^= RCON[i / KEYSIZE];
+            tmp[0] ^= RCON[i / nk];
+        } else if key_size == KeySize::Aes256 && i % nk == 16 {
+            // AES-256 extra SubWord
+            for j in 0..4 {
+                tmp[j] = SBOX[tmp[j] as usize];
+            }
         }
 
         for j in tmp {
-            xk[i] = xk[i - KEYSIZE] ^ j;
+            xk[i] = xk[i - nk] ^ j;
             i += 1;
         }
     }
 
     xk
 }
+
+pub fn key_expansion_fhe(
+    key: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<EncryptedKeySchedule, AesFheError> {
+    Ok(key_expansion_with_fhe(key, &SboxCircuit, sk)?.0)
+}
+
+pub fn key_expansion_with_fhe(
+    key: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(EncryptedKeySchedule, Metrics), AesFheError> {
+    let key_size = KeySize::from_key_len(key.len()).ok_or(AesFheError::KeyLength(key.len()))?;
+    check_params(key, sk)?;
+    let nk = key_size.key_len();
+    let xk_len = key_size.round_key_size();
+
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut metrics = Metrics::new();
+
+    let mut xk = Vec::with_capacity(xk_len);
+    xk.extend_from_slice(key);
+
+    metrics.run(|m| {
+        let mut i = nk;
+
+        while i < xk_len {
+            let mut tmp = xk[i - 4..i].to_vec();
+
+            if i.is_multiple_of(nk) {
+                tmp.rotate_left(1);
+                m.time("sub_word", || {
+                    tmp.par_iter_mut().for_each(|elem| {
+                        *elem = sbox.sbox(elem, &pos_vals, sk);
+                    })
+                });
+                let rcon = sk.create_trivial_radix(RCON[i / nk], num_blocks(sk));
+                tmp[0] = sk.unchecked_bitxor(&tmp[0], &rcon);
+            } else if key_size == KeySize::Aes256 && i % nk == 16 {
+                // AES-256 extra SubWord
+                m.time("sub_word", || {
+                    tmp.par_iter_mut().for_each(|elem| {
+                        *elem = sbox.sbox(elem, &pos_vals, sk);
+                    })
+                });
+            }
+
+            let word: Vec<BaseRadixCiphertext<Ciphertext>> = m.time("xor_word", || {
+                tmp.par_iter()
+                    .enumerate()
+                    .map(|(j, elem)| sk.unchecked_bitxor(&xk[i - nk + j], elem))
+                    .collect()
+            });
+
+            xk.extend(word);
+            i += 4;
+        }
+    });
+
+    Ok((EncryptedKeySchedule::new(xk)?, metrics))
+}
@@ -0,0 +1,307 @@
+use crate::aes128_keyschedule::BLOCKSIZE;
+use crate::aes128_rdx_fhe::{
+    InvMixColumns, add_round_key_fhe, decrypt_states_with_fhe, encrypt_state_with_fhe,
+    encrypt_states_with_fhe,
+};
+use crate::aes128_sbox::{SboxBackend, SboxCircuit};
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
+
+use tfhe::integer::ServerKey;
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::shortint::Ciphertext;
+
+use rayon::prelude::*;
+
+// SP 800-38A B.1, the counter is the whole block as a big endian integer
+#[inline]
+pub fn ctr_block(nonce: &[u8; BLOCKSIZE], i: usize) -> [u8; BLOCKSIZE] {
+    u128::from_be_bytes(*nonce)
+        .wrapping_add(i as u128)
+        .to_be_bytes()
+}
+
+pub fn counter_keystream_fhe(
+    xk: &EncryptedKeySchedule,
+    counters: &[[u8; BLOCKSIZE]],
+    sk: &ServerKey,
+) -> Result<Vec<EncryptedState>, AesFheError> {
+    Ok(counter_keystream_with_fhe(xk, counters, &SboxCircuit, sk)?.0)
+}
+
+pub fn counter_keystream_with_fhe(
+    xk: &EncryptedKeySchedule,
+    counters: &[[u8; BLOCKSIZE]],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    let ctrs_ck: Vec<EncryptedState> = counters
+        .iter()
+        .map(|ctr| EncryptedState::trivial(ctr, sk))
+        .collect();
+
+    encrypt_states_with_fhe(&ctrs_ck, xk, sbox, sk)
+}
+
+pub fn ctr_keystream_fhe(
+    xk: &EncryptedKeySchedule,
+    nonce: &[u8; BLOCKSIZE],
+    n_blocks: usize,
+    sk: &ServerKey,
+) -> Result<Vec<EncryptedState>, AesFheError> {
+    Ok(ctr_keystream_with_fhe(xk, nonce, n_blocks, &SboxCircuit, sk)?.0)
+}
+
+pub fn ctr_keystream_with_fhe(
+    xk: &EncryptedKeySchedule,
+    nonce: &[u8; BLOCKSIZE],
+    n_blocks: usize,
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    let counters: Vec<[u8; BLOCKSIZE]> = (0..n_blocks).map(|i| ctr_block(nonce, i)).collect();
+
+    counter_keystream_with_fhe(xk, &counters, sbox, sk)
+}
+
+// AES-CTR ciphertext in, FHE ciphertext of the plaintext out
+pub fn ctr_transcipher_fhe(
+    xk: &EncryptedKeySchedule,
+    nonce: &[u8; BLOCKSIZE],
+    data: &[u8],
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    Ok(ctr_transcipher_with_fhe(xk, nonce, data, &SboxCircuit, sk)?.0)
+}
+
+pub fn ctr_transcipher_with_fhe(
+    xk: &EncryptedKeySchedule,
+    nonce: &[u8; BLOCKSIZE],
+    data: &[u8],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    let n_blocks = data.len().div_ceil(BLOCKSIZE);
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let (keystream, ks_metrics) = ctr_keystream_with_fhe(xk, nonce, n_blocks, sbox, sk)?;
+        m.nest(&ks_metrics);
+
+        let keystream = into_bytes(keystream);
+
+        Ok::<_, AesFheError>(m.time("keystream_xor", || {
+            keystream
+                .par_iter()
+                .zip(data.par_iter())
+                .map(|(ks, &byte)| sk.unchecked_scalar_bitxor_parallelized(ks, byte))
+                .collect()
+        }))
+    })?;
+
+    Ok((out, metrics))
+}
+
+#[inline]
+fn into_bytes(states: Vec<EncryptedState>) -> Vec<BaseRadixCiphertext<Ciphertext>> {
+    states
+        .into_iter()
+        .flat_map(EncryptedState::into_vec)
+        .collect()
+}
+
+pub fn ofb_keystream_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    n_blocks: usize,
+    sk: &ServerKey,
+) -> Result<Vec<EncryptedState>, AesFheError> {
+    Ok(ofb_keystream_with_fhe(xk, iv, n_blocks, &SboxCircuit, sk)?.0)
+}
+
+pub fn ofb_keystream_with_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    n_blocks: usize,
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    let mut metrics = Metrics::new();
+
+    let keystream = metrics.run(|m| {
+        let mut keystream = Vec::with_capacity(n_blocks);
+        let mut state_ck = iv.clone();
+        for _ in 0..n_blocks {
+            let (next, block_metrics) = encrypt_state_with_fhe(&state_ck, xk, sbox, sk)?;
+            m.nest(&block_metrics);
+            state_ck = next;
+            keystream.push(state_ck.clone());
+        }
+
+        Ok::<_, AesFheError>(keystream)
+    })?;
+
+    Ok((keystream, metrics))
+}
+
+// OFB encryption and decryption are the same operation, the last block may be partial
+pub fn ofb_xor_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    msg: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    Ok(ofb_xor_with_fhe(xk, iv, msg, &SboxCircuit, sk)?.0)
+}
+
+pub fn ofb_xor_with_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    msg: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    let n_blocks = msg.len().div_ceil(BLOCKSIZE);
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let (keystream, ks_metrics) = ofb_keystream_with_fhe(xk, iv, n_blocks, sbox, sk)?;
+        m.nest(&ks_metrics);
+
+        let keystream = into_bytes(keystream);
+
+        Ok::<_, AesFheError>(m.time("keystream_xor", || {
+            keystream
+                .par_iter()
+                .zip(msg.par_iter())
+                .map(|(ks, m)| sk.unchecked_bitxor(ks, m))
+                .collect()
+        }))
+    })?;
+
+    Ok((out, metrics))
+}
+
+pub fn ofb_xor_plain_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    msg: &[u8],
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    Ok(ofb_xor_plain_with_fhe(xk, iv, msg, &SboxCircuit, sk)?.0)
+}
+
+pub fn ofb_xor_plain_with_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    msg: &[u8],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    let n_blocks = msg.len().div_ceil(BLOCKSIZE);
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let (keystream, ks_metrics) = ofb_keystream_with_fhe(xk, iv, n_blocks, sbox, sk)?;
+        m.nest(&ks_metrics);
+
+        let keystream = into_bytes(keystream);
+
+        Ok::<_, AesFheError>(m.time("keystream_xor", || {
+            keystream
+                .par_iter()
+                .zip(msg.par_iter())
+                .map(|(ks, &byte)| sk.unchecked_scalar_bitxor_parallelized(ks, byte))
+                .collect()
+        }))
+    })?;
+
+    Ok((out, metrics))
+}
+
+pub fn cbc_encrypt_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    blocks: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    Ok(cbc_encrypt_with_fhe(xk, iv, blocks, &SboxCircuit, sk)?.0)
+}
+
+pub fn cbc_encrypt_with_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    blocks: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    AesFheError::check_len(blocks.len(), BLOCKSIZE)?;
+
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let mut out = Vec::with_capacity(blocks.len());
+        let mut prev = iv.clone();
+        for block in blocks.chunks_exact(BLOCKSIZE) {
+            let mut state_ck = EncryptedState::new(block.to_vec())?;
+            m.time("chain_xor", || add_round_key_fhe(&mut state_ck, &prev, sk))?;
+            let (next, block_metrics) = encrypt_state_with_fhe(&state_ck, xk, sbox, sk)?;
+            m.nest(&block_metrics);
+            prev = next;
+            out.extend_from_slice(&prev);
+        }
+
+        Ok::<_, AesFheError>(out)
+    })?;
+
+    Ok((out, metrics))
+}
+
+// every block only depends on ciphertext, so all blocks are decrypted as one batch
+pub fn cbc_decrypt_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    blocks: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    Ok(cbc_decrypt_with_fhe(xk, iv, blocks, &SboxCircuit, InvMixColumns::default(), sk)?.0)
+}
+
+pub fn cbc_decrypt_with_fhe(
+    xk: &EncryptedKeySchedule,
+    iv: &EncryptedState,
+    blocks: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    inv_mix: InvMixColumns,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    AesFheError::check_len(blocks.len(), BLOCKSIZE)?;
+
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let states = blocks
+            .chunks_exact(BLOCKSIZE)
+            .map(|block| EncryptedState::new(block.to_vec()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (mut out, batch_metrics) = decrypt_states_with_fhe(&states, xk, sbox, inv_mix, sk)?;
+        m.nest(&batch_metrics);
+
+        m.time("chain_xor", || {
+            out.par_iter_mut()
+                .enumerate()
+                .try_for_each(|(i, state_ck)| {
+                    let prev = match i {
+                        0 => &iv[..],
+                        _ => &blocks[(i - 1) * BLOCKSIZE..i * BLOCKSIZE],
+                    };
+                    add_round_key_fhe(state_ck, prev, sk)
+                })
+        })?;
+
+        Ok::<_, AesFheError>(into_bytes(out))
+    })?;
+
+    Ok((out, metrics))
+}
@@ -1,8 +1,12 @@
-use crate::aes_fhe::{NUM_BLOCK, dec_rdx_vec, enc_rdx_vec, gen_rdx_keys, print_hex_rdx_fhe};
+use crate::aes_fhe::{check_params, enc_round_keys_fhe, enc_state_fhe, gen_rdx_keys, num_blocks};
 
-use crate::aes128_bool_circ::{PosVals, mix_cols, sbox_idx, sbox_inv_idx};
-use crate::aes128_keyschedule::{BLOCKSIZE, KEYSIZE, ROUNDKEYSIZE, ROUNDS};
+use crate::aes128_bool_circ::{PosVals, inv_mix_cols, mix_cols};
+use crate::aes128_keyschedule::BLOCKSIZE;
+use crate::aes128_sbox::{SboxBackend, SboxCircuit};
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
 use crate::aes128_tables::{GMUL9, GMULB, GMULD, GMULE, gen_tbl};
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
 
 use tfhe::MatchValues;
 use tfhe::integer::{RadixClientKey, ServerKey};
@@ -10,61 +14,65 @@ use tfhe::integer::{RadixClientKey, ServerKey};
 use tfhe::integer::ciphertext::BaseRadixCiphertext;
 use tfhe::shortint::Ciphertext;
 
-use std::time::Instant;
-
 use rayon::prelude::*;
 
 #[inline]
-fn add_round_key_fhe(
+pub fn add_round_key_fhe(
     state: &mut [BaseRadixCiphertext<Ciphertext>],
     rkey: &[BaseRadixCiphertext<Ciphertext>],
     sk: &ServerKey,
-) {
-    let start = Instant::now();
+) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+    if rkey.len() != BLOCKSIZE {
+        return Err(AesFheError::RoundKeyLength(rkey.len()));
+    }
 
     state.par_iter_mut().enumerate().for_each(|(i, elem)| {
-        *elem = sk.unchecked_bitxor(elem, &rkey[i]);
+        *elem = sk.unchecked_bitxor(elem, &rkey[i % BLOCKSIZE]);
     });
 
-    println!("add_round_key_fhe       {:.2?}", start.elapsed());
+    Ok(())
 }
 
 #[inline]
 pub fn sub_bytes_fhe(
     state: &mut [BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
     pos_vals: &PosVals,
     sk: &ServerKey,
 ) {
-    let start = Instant::now();
     let tmp = state.to_vec();
 
     state.par_iter_mut().enumerate().for_each(|(i, elem)| {
-        *elem = sbox_idx(&tmp[i], pos_vals, sk);
+        *elem = sbox.sbox(&tmp[i], pos_vals, sk);
     });
-
-    println!("sub_bytes_fhe           {:.2?}", start.elapsed());
 }
 
 #[inline]
 pub fn inv_sub_bytes_fhe(
     state: &mut [BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
     pos_vals: &PosVals,
     sk: &ServerKey,
 ) {
-    let start = Instant::now();
-    assert!(state.len() % 2 == 0);
     let tmp = state.to_vec();
 
     state.par_iter_mut().enumerate().for_each(|(i, elem)| {
-        *elem = sbox_inv_idx(&tmp[i], pos_vals, sk);
+        *elem = sbox.sbox_inv(&tmp[i], pos_vals, sk);
     });
+}
+
+// a batch of states is permuted block by block
+#[inline]
+pub fn shift_rows_fhe(state: &mut [BaseRadixCiphertext<Ciphertext>]) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+    state.chunks_exact_mut(BLOCKSIZE).for_each(shift_rows_block);
 
-    println!("inv_sub_bytes_fhe   {:.2?}", start.elapsed());
+    Ok(())
 }
 
 #[inline]
-fn shift_rows_fhe(state: &mut [BaseRadixCiphertext<Ciphertext>]) {
-    let start = Instant::now();
+fn shift_rows_block(state: &mut [BaseRadixCiphertext<Ciphertext>]) {
     let tmp = state.to_vec();
 
     // col. 0
@@ -90,13 +98,22 @@ fn shift_rows_fhe(state: &mut [BaseRadixCiphertext<Ciphertext>]) {
     state[13] = tmp[1].clone();
     state[14] = tmp[6].clone();
     state[15] = tmp[11].clone();
+}
 
-    println!("shift_rows_fhe          {:.2?}", start.elapsed());
+#[inline]
+pub fn inv_shift_rows_fhe(
+    state: &mut [BaseRadixCiphertext<Ciphertext>],
+) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+    state
+        .chunks_exact_mut(BLOCKSIZE)
+        .for_each(inv_shift_rows_block);
+
+    Ok(())
 }
 
 #[inline]
-fn inv_shift_rows_fhe(state: &mut [BaseRadixCiphertext<Ciphertext>]) {
-    let start = Instant::now();
+fn inv_shift_rows_block(state: &mut [BaseRadixCiphertext<Ciphertext>]) {
     let tmp = state.to_vec();
 
     // col. 0
@@ -122,8 +139,6 @@ fn inv_shift_rows_fhe(state: &mut [BaseRadixCiphertext<Ciphertext>]) {
     state[13] = tmp[9].clone();
     state[14] = tmp[6].clone();
     state[15] = tmp[3].clone();
-
-    println!("inv_shift_rows_fhe      {:.2?}", start.elapsed());
 }
 
 #[inline]
@@ -132,18 +147,11 @@ fn lut_state(
     tbl: &MatchValues<u8>,
     sk: &ServerKey,
 ) -> [BaseRadixCiphertext<Ciphertext>; 16] {
-    let start = Instant::now();
-    assert!(state.len() == 16);
-
-    let mut tmp = state.to_vec();
+    let mut tmp: [BaseRadixCiphertext<Ciphertext>; 16] = core::array::from_fn(|i| state[i].clone());
     tmp.par_iter_mut().enumerate().for_each(|(i, elem)| {
         (*elem, _) = sk.unchecked_match_value_parallelized(&state[i], tbl);
     });
 
-    println!("m_col lut time         {:.2?}", start.elapsed());
-    let tmp: [BaseRadixCiphertext<Ciphertext>; 16] =
-        tmp.try_into().expect("Expected a Vec of length 16");
-
     tmp
 }
 
@@ -156,7 +164,6 @@ fn parallel_xor(
     idx2: &[usize],
     sk: &ServerKey,
 ) {
-    let start = Instant::now();
     assert!(idx1.len() == 4);
     assert!(idx2.len() == 4);
 
@@ -171,18 +178,15 @@ fn parallel_xor(
             let p: usize = i % 4;
             *elem = sk.unchecked_bitxor(&g1_state[c + idx1[p]], &g2_state[c + idx2[p]]);
         });
-
-    println!("m_col gx xor gy time    {:.2?}", start.elapsed());
 }
 
 #[inline]
-fn mix_columns_fhe(
+pub fn mix_columns_fhe(
     state: &mut [BaseRadixCiphertext<Ciphertext>],
     pos_vals: &PosVals,
     sk: &ServerKey,
-) {
-    let start = Instant::now();
-    assert!(state.len() == 16);
+) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
 
     state.par_chunks_exact_mut(4).for_each(|col| {
         let col_clone = [
@@ -200,11 +204,53 @@ fn mix_columns_fhe(
         col[3] = out[3].clone();
     });
 
-    println!("m_col time              {:.2?}", start.elapsed());
+    Ok(())
+}
+
+#[inline]
+pub fn inv_mix_columns_bc_fhe(
+    state: &mut [BaseRadixCiphertext<Ciphertext>],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+
+    state.par_chunks_exact_mut(4).for_each(|col| {
+        let col_clone = [
+            col[0].clone(),
+            col[1].clone(),
+            col[2].clone(),
+            col[3].clone(),
+        ];
+
+        let out = inv_mix_cols(&col_clone, pos_vals, sk);
+
+        col.clone_from_slice(&out);
+    });
+
+    Ok(())
 }
 
 #[inline]
-fn inv_mix_columns_fhe(
+pub fn inv_mix_columns_fhe(
+    state: &mut [BaseRadixCiphertext<Ciphertext>],
+    gmul9_tbl: &MatchValues<u8>,
+    gmulb_tbl: &MatchValues<u8>,
+    gmuld_tbl: &MatchValues<u8>,
+    gmule_tbl: &MatchValues<u8>,
+    sk: &ServerKey,
+) -> Result<(), AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+
+    state.par_chunks_exact_mut(BLOCKSIZE).for_each(|block| {
+        inv_mix_columns_block(block, gmul9_tbl, gmulb_tbl, gmuld_tbl, gmule_tbl, sk)
+    });
+
+    Ok(())
+}
+
+#[inline]
+fn inv_mix_columns_block(
     state: &mut [BaseRadixCiphertext<Ciphertext>],
     gmul9_tbl: &MatchValues<u8>,
     gmulb_tbl: &MatchValues<u8>,
@@ -212,7 +258,6 @@ fn inv_mix_columns_fhe(
     gmule_tbl: &MatchValues<u8>,
     sk: &ServerKey,
 ) {
-    let start = Instant::now();
     assert!(state.len() == 16);
 
     let g9_state = lut_state(state, gmul9_tbl, sk);
@@ -221,7 +266,7 @@ fn inv_mix_columns_fhe(
     let ge_state = lut_state(state, gmule_tbl, sk);
 
     let mut binding: Vec<BaseRadixCiphertext<Ciphertext>> = (0..16)
-        .map(|_| sk.create_trivial_radix(0, NUM_BLOCK))
+        .map(|_| sk.create_trivial_radix(0, num_blocks(sk)))
         .collect();
     let g9_gb_xor = binding.as_mut_slice();
     let g9_idx = vec![3, 0, 1, 2];
@@ -229,7 +274,7 @@ fn inv_mix_columns_fhe(
     parallel_xor(g9_gb_xor, &g9_state, &gb_state, &g9_idx, &gb_idx, sk);
 
     let mut binding: Vec<BaseRadixCiphertext<Ciphertext>> = (0..16)
-        .map(|_| sk.create_trivial_radix(0, NUM_BLOCK))
+        .map(|_| sk.create_trivial_radix(0, num_blocks(sk)))
         .collect();
     let gd_ge_xor = binding.as_mut_slice();
     let gd_idx = vec![2, 3, 0, 1];
@@ -242,191 +287,357 @@ fn inv_mix_columns_fhe(
         .for_each(|(i, state_elem)| {
             *state_elem = sk.unchecked_bitxor(&g9_gb_xor[i], &gd_ge_xor[i]);
         });
-
-    println!("inv_mix_columns_fhe time {:.2?}", start.elapsed());
 }
 
-pub fn encrypt_one_block_fhe(
-    input: &[u8; KEYSIZE],
-    xk: &[u8; ROUNDKEYSIZE],
-    output: &mut [u8; BLOCKSIZE],
+pub fn encrypt_state_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
     sk: &ServerKey,
-    ck: &RadixClientKey,
-) {
-    let mut state = [0u8; BLOCKSIZE];
-    state.copy_from_slice(input);
+) -> Result<EncryptedState, AesFheError> {
+    Ok(encrypt_state_with_fhe(state, xk, &SboxCircuit, sk)?.0)
+}
 
-    let pos_vals = PosVals::new(ck);
+// like every FHE step in the crate, the rounds run on the rayon pool current
+// at the call: the global one, or the one of ExecContext::install
+pub fn encrypt_state_with_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(EncryptedState, Metrics), AesFheError> {
+    let (state_ck, metrics) = encrypt_rounds_fhe(state, xk, sbox, &mut |_, _, _| {}, sk)?;
 
-    let mut state_ck = enc_rdx_vec(&state, ck);
-    let xk_ck = enc_rdx_vec(xk, ck);
+    Ok((EncryptedState::new(state_ck)?, metrics))
+}
 
-    let start = Instant::now();
+// the batch, the round keys and the server key must belong together
+#[inline]
+fn check_rounds(
+    state: &[BaseRadixCiphertext<Ciphertext>],
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<usize, AesFheError> {
+    AesFheError::check_len(state.len(), BLOCKSIZE)?;
+    check_params(state, sk)?;
+    check_params(xk, sk)?;
 
-    print_hex_rdx_fhe("input", 0, &state_ck, ck);
-    add_round_key_fhe(&mut state_ck, &xk_ck[..2 * BLOCKSIZE], sk);
-    print_hex_rdx_fhe("k_sch", 0, &state_ck, ck);
+    Ok(xk.rounds())
+}
 
-    for round in 1..ROUNDS {
-        sub_bytes_fhe(&mut state_ck, &pos_vals, sk);
-        print_hex_rdx_fhe("s_box", round, &state_ck, ck);
+// called after every step with the round, the step name and the state
+pub(crate) type StepHook<'a> = dyn FnMut(usize, &str, &[BaseRadixCiphertext<Ciphertext>]) + 'a;
 
-        shift_rows_fhe(&mut state_ck);
-        print_hex_rdx_fhe("s_row", round, &state_ck, ck);
+#[inline]
+fn step<R>(
+    m: &mut Metrics,
+    hook: &mut StepHook,
+    round: usize,
+    name: &str,
+    state: &mut [BaseRadixCiphertext<Ciphertext>],
+    f: impl FnOnce(&mut [BaseRadixCiphertext<Ciphertext>]) -> R,
+) -> R {
+    let res = m.time(name, || f(state));
+    hook(round, name, state);
 
-        mix_columns_fhe(&mut state_ck, &pos_vals, sk);
-        print_hex_rdx_fhe("m_col", round, &state_ck, ck);
+    res
+}
 
-        add_round_key_fhe(&mut state_ck, &xk_ck[round * KEYSIZE..ROUNDKEYSIZE], sk);
-        print_hex_rdx_fhe("k_sch", round, &state_ck, ck);
-    }
+pub(crate) fn encrypt_rounds_fhe(
+    state: &[BaseRadixCiphertext<Ciphertext>],
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    hook: &mut StepHook,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    let rounds = check_rounds(state, xk, sk)?;
+
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut state_ck = state.to_vec();
+    let mut metrics = Metrics::new();
+
+    metrics.run(|m| {
+        m.round(|m| {
+            step(m, hook, 0, "add_round_key", &mut state_ck, |s| {
+                add_round_key_fhe(s, xk.round_key(0), sk)
+            })
+        })?;
+
+        for round in 1..rounds {
+            m.round(|m| {
+                step(m, hook, round, "sub_bytes", &mut state_ck, |s| {
+                    sub_bytes_fhe(s, sbox, &pos_vals, sk)
+                });
+                step(m, hook, round, "shift_rows", &mut state_ck, shift_rows_fhe)?;
+                step(m, hook, round, "mix_columns", &mut state_ck, |s| {
+                    mix_columns_fhe(s, &pos_vals, sk)
+                })?;
+                step(m, hook, round, "add_round_key", &mut state_ck, |s| {
+                    add_round_key_fhe(s, xk.round_key(round), sk)
+                })
+            })?;
+        }
 
-    sub_bytes_fhe(&mut state_ck, &pos_vals, sk);
-    print_hex_rdx_fhe("s_box", 10, &state_ck, ck);
+        m.round(|m| {
+            step(m, hook, rounds, "sub_bytes", &mut state_ck, |s| {
+                sub_bytes_fhe(s, sbox, &pos_vals, sk)
+            });
+            step(m, hook, rounds, "shift_rows", &mut state_ck, shift_rows_fhe)?;
+            step(m, hook, rounds, "add_round_key", &mut state_ck, |s| {
+                add_round_key_fhe(s, xk.round_key(rounds), sk)
+            })
+        })
+    })?;
+
+    Ok((state_ck, metrics))
+}
 
-    shift_rows_fhe(&mut state_ck);
-    print_hex_rdx_fhe("s_row", 10, &state_ck, ck);
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InvMixColumns {
+    // four lookups per byte
+    #[default]
+    MatchValues,
+    // MixColumns after an XOR-only preprocessing step
+    BoolCircuit,
+}
 
-    add_round_key_fhe(&mut state_ck, &xk_ck[KEYSIZE * ROUNDS..ROUNDKEYSIZE], sk);
-    print_hex_rdx_fhe("k_sch", 10, &state_ck, ck);
+pub fn decrypt_state_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<EncryptedState, AesFheError> {
+    Ok(decrypt_state_with_fhe(state, xk, &SboxCircuit, InvMixColumns::default(), sk)?.0)
+}
 
-    println!("encrypt_block_fhe         {:.2?}", start.elapsed());
+pub fn decrypt_state_with_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    inv_mix: InvMixColumns,
+    sk: &ServerKey,
+) -> Result<(EncryptedState, Metrics), AesFheError> {
+    let (state_ck, metrics) = decrypt_rounds_fhe(state, xk, sbox, inv_mix, &mut |_, _, _| {}, sk)?;
 
-    let output_vec = dec_rdx_vec(&state_ck, ck);
-    output.copy_from_slice(&output_vec);
+    Ok((EncryptedState::new(state_ck)?, metrics))
 }
 
-pub fn encrypt_block_fhe(
-    input: &[u8; KEYSIZE],
-    xk: &[u8; ROUNDKEYSIZE],
-    output: &mut [u8; BLOCKSIZE],
-    iter: usize,
-) {
-    let mut state = [0u8; BLOCKSIZE];
-    state.copy_from_slice(input);
+// rounds are numbered in the order they run, round 0 uses the last round key
+pub(crate) fn decrypt_rounds_fhe(
+    state: &[BaseRadixCiphertext<Ciphertext>],
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    inv_mix: InvMixColumns,
+    hook: &mut StepHook,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    let rounds = check_rounds(state, xk, sk)?;
+
+    let pos_vals = PosVals::new_trivial(sk);
+    let mut state_ck = state.to_vec();
+    let mut metrics = Metrics::new();
+
+    metrics.run(|m| {
+        // the tables are only needed by the lookup variant, built inside the
+        // run so they count towards the total
+        let gmul_tbls = match inv_mix {
+            InvMixColumns::MatchValues => {
+                Some([&GMUL9, &GMULB, &GMULD, &GMULE].map(|tbl| m.time("gen_tbl", || gen_tbl(tbl))))
+            }
+            InvMixColumns::BoolCircuit => None,
+        };
+
+        m.round(|m| {
+            step(m, hook, 0, "add_round_key", &mut state_ck, |s| {
+                add_round_key_fhe(s, xk.round_key(rounds), sk)
+            })
+        })?;
+
+        for round in (1..rounds).rev() {
+            let r = rounds - round;
+            m.round(|m| {
+                step(
+                    m,
+                    hook,
+                    r,
+                    "inv_shift_rows",
+                    &mut state_ck,
+                    inv_shift_rows_fhe,
+                )?;
+                step(m, hook, r, "inv_sub_bytes", &mut state_ck, |s| {
+                    inv_sub_bytes_fhe(s, sbox, &pos_vals, sk)
+                });
+                step(m, hook, r, "add_round_key", &mut state_ck, |s| {
+                    add_round_key_fhe(s, xk.round_key(round), sk)
+                })?;
+                step(
+                    m,
+                    hook,
+                    r,
+                    "inv_mix_columns",
+                    &mut state_ck,
+                    |s| match &gmul_tbls {
+                        Some([gmul9_tbl, gmulb_tbl, gmuld_tbl, gmule_tbl]) => {
+                            inv_mix_columns_fhe(s, gmul9_tbl, gmulb_tbl, gmuld_tbl, gmule_tbl, sk)
+                        }
+                        None => inv_mix_columns_bc_fhe(s, &pos_vals, sk),
+                    },
+                )
+            })?;
+        }
 
-    println!("generate_keys");
-    let (ck, sk) = gen_rdx_keys();
-    let pos_vals = PosVals::new(&ck);
+        m.round(|m| {
+            step(
+                m,
+                hook,
+                rounds,
+                "inv_shift_rows",
+                &mut state_ck,
+                inv_shift_rows_fhe,
+            )?;
+            step(m, hook, rounds, "inv_sub_bytes", &mut state_ck, |s| {
+                inv_sub_bytes_fhe(s, sbox, &pos_vals, sk)
+            });
+            step(m, hook, rounds, "add_round_key", &mut state_ck, |s| {
+                add_round_key_fhe(s, xk.round_key(0), sk)
+            })
+        })
+    })?;
+
+    Ok((state_ck, metrics))
+}
 
-    let mut state_ck = enc_rdx_vec(&state, &ck);
-    let xk_ck = enc_rdx_vec(xk, &ck);
+// many states under one key schedule go through the rounds side by side, every
+// step is a single parallel loop over the bytes or columns of the whole batch
+pub fn encrypt_states_fhe(
+    states: &[EncryptedState],
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<Vec<EncryptedState>, AesFheError> {
+    Ok(encrypt_states_with_fhe(states, xk, &SboxCircuit, sk)?.0)
+}
 
-    let tot = Instant::now();
-    for i in 1..=iter {
-        println!("Encrypting iteration: {}", i);
+pub fn encrypt_states_with_fhe(
+    states: &[EncryptedState],
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    if states.is_empty() {
+        return Ok((Vec::new(), Metrics::new()));
+    }
 
-        let start = Instant::now();
+    let batch = flatten_states(states);
+    let (batch, metrics) = encrypt_rounds_fhe(&batch, xk, sbox, &mut |_, _, _| {}, sk)?;
 
-        print_hex_rdx_fhe("input", 0, &state_ck, &ck);
-        add_round_key_fhe(&mut state_ck, &xk_ck[..2 * BLOCKSIZE], &sk);
-        print_hex_rdx_fhe("k_sch", 0, &state_ck, &ck);
+    Ok((split_states(batch)?, metrics))
+}
 
-        for round in 1..ROUNDS {
-            sub_bytes_fhe(&mut state_ck, &pos_vals, &sk);
-            print_hex_rdx_fhe("s_box", round, &state_ck, &ck);
+pub fn decrypt_states_fhe(
+    states: &[EncryptedState],
+    xk: &EncryptedKeySchedule,
+    sk: &ServerKey,
+) -> Result<Vec<EncryptedState>, AesFheError> {
+    Ok(decrypt_states_with_fhe(states, xk, &SboxCircuit, InvMixColumns::default(), sk)?.0)
+}
 
-            shift_rows_fhe(&mut state_ck);
-            print_hex_rdx_fhe("s_row", round, &state_ck, &ck);
+pub fn decrypt_states_with_fhe(
+    states: &[EncryptedState],
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    inv_mix: InvMixColumns,
+    sk: &ServerKey,
+) -> Result<(Vec<EncryptedState>, Metrics), AesFheError> {
+    if states.is_empty() {
+        return Ok((Vec::new(), Metrics::new()));
+    }
 
-            mix_columns_fhe(&mut state_ck, &pos_vals, &sk);
-            print_hex_rdx_fhe("m_col", round, &state_ck, &ck);
+    let batch = flatten_states(states);
+    let (batch, metrics) = decrypt_rounds_fhe(&batch, xk, sbox, inv_mix, &mut |_, _, _| {}, sk)?;
 
-            add_round_key_fhe(&mut state_ck, &xk_ck[round * KEYSIZE..ROUNDKEYSIZE], &sk);
-            print_hex_rdx_fhe("k_sch", round, &state_ck, &ck);
-        }
+    Ok((split_states(batch)?, metrics))
+}
 
-        sub_bytes_fhe(&mut state_ck, &pos_vals, &sk);
-        print_hex_rdx_fhe("s_box", 10, &state_ck, &ck);
+#[inline]
+pub(crate) fn flatten_states(states: &[EncryptedState]) -> Vec<BaseRadixCiphertext<Ciphertext>> {
+    states
+        .iter()
+        .flat_map(|state| state.iter().cloned())
+        .collect()
+}
 
-        shift_rows_fhe(&mut state_ck);
-        print_hex_rdx_fhe("s_row", 10, &state_ck, &ck);
+#[inline]
+pub(crate) fn split_states(
+    batch: Vec<BaseRadixCiphertext<Ciphertext>>,
+) -> Result<Vec<EncryptedState>, AesFheError> {
+    batch
+        .chunks_exact(BLOCKSIZE)
+        .map(|state| EncryptedState::new(state.to_vec()))
+        .collect()
+}
 
-        add_round_key_fhe(&mut state_ck, &xk_ck[KEYSIZE * ROUNDS..ROUNDKEYSIZE], &sk);
-        print_hex_rdx_fhe("k_sch", 10, &state_ck, &ck);
+pub fn encrypt_one_block_fhe(
+    input: &[u8; BLOCKSIZE],
+    xk: &[u8],
+    output: &mut [u8; BLOCKSIZE],
+    sk: &ServerKey,
+    ck: &RadixClientKey,
+) -> Result<(), AesFheError> {
+    let state_ck = enc_state_fhe(input, ck);
+    let xk_ck = enc_round_keys_fhe(xk, ck)?;
 
-        println!("encrypt_block_fhe         {:.2?}", start.elapsed());
-    }
-    let elapsed = tot.elapsed();
-    println!("AES of #{iter} outputs computed in: {elapsed:?}");
+    let state_ck = encrypt_state_fhe(&state_ck, &xk_ck, sk)?;
+    *output = state_ck.decrypt(ck);
 
-    let output_vec = dec_rdx_vec(&state_ck, &ck);
-    output.copy_from_slice(&output_vec);
-    println!("outpt_vec {:?}", output_vec);
-    println!("outpt     {:?}", output);
+    Ok(())
 }
 
-pub fn decrypt_block_fhe(
+pub fn encrypt_block_fhe(
     input: &[u8; BLOCKSIZE],
-    xk: &[u8; ROUNDKEYSIZE],
+    xk: &[u8],
     output: &mut [u8; BLOCKSIZE],
     iter: usize,
-) {
-    let mut state = [0u8; BLOCKSIZE];
-    state.copy_from_slice(input);
-
-    println!("generate_keys");
+) -> Result<Metrics, AesFheError> {
     let (ck, sk) = gen_rdx_keys();
-    let pos_vals = PosVals::new(&ck);
-
-    let mut state_ck = enc_rdx_vec(&state, &ck);
-    let xk_ck = enc_rdx_vec(xk, &ck);
-
-    println!("generate_match_value_tables");
-    let gmul9_tbl = gen_tbl(&GMUL9);
-    let gmulb_tbl = gen_tbl(&GMULB);
-    let gmuld_tbl = gen_tbl(&GMULD);
-    let gmule_tbl = gen_tbl(&GMULE);
-
-    let tot = Instant::now();
-    for i in 1..=iter {
-        println!("Decrypting iteration: {}", i);
 
-        let start = Instant::now();
+    let mut state_ck = enc_state_fhe(input, &ck);
+    let xk_ck = enc_round_keys_fhe(xk, &ck)?;
 
-        print_hex_rdx_fhe("iinput", 0, &state_ck, &ck);
-        add_round_key_fhe(&mut state_ck, &xk_ck[KEYSIZE * ROUNDS..ROUNDKEYSIZE], &sk);
-        print_hex_rdx_fhe("ik_sch", 0, &state_ck, &ck);
-
-        for round in (1..ROUNDS).rev() {
-            inv_shift_rows_fhe(&mut state_ck);
-            print_hex_rdx_fhe("is_row", round, &state_ck, &ck);
-
-            inv_sub_bytes_fhe(&mut state_ck, &pos_vals, &sk);
-            print_hex_rdx_fhe("is_box", round, &state_ck, &ck);
-
-            add_round_key_fhe(
-                &mut state_ck,
-                &xk_ck[round * KEYSIZE..(round + 1) * KEYSIZE],
-                &sk,
-            );
-            print_hex_rdx_fhe("ik_sch", round, &state_ck, &ck);
-
-            inv_mix_columns_fhe(
-                &mut state_ck,
-                &gmul9_tbl,
-                &gmulb_tbl,
-                &gmuld_tbl,
-                &gmule_tbl,
-                &sk,
-            );
-            print_hex_rdx_fhe("ik_add", round, &state_ck, &ck);
-        }
+    let mut metrics = Metrics::new();
+    for _ in 0..iter {
+        let (out, m) = encrypt_state_with_fhe(&state_ck, &xk_ck, &SboxCircuit, &sk)?;
+        state_ck = out;
+        metrics.merge(&m);
+    }
 
-        inv_shift_rows_fhe(&mut state_ck);
-        print_hex_rdx_fhe("is_row", 0, &state_ck, &ck);
+    *output = state_ck.decrypt(&ck);
 
-        inv_sub_bytes_fhe(&mut state_ck, &pos_vals, &sk);
-        print_hex_rdx_fhe("is_box", 0, &state_ck, &ck);
+    Ok(metrics)
+}
 
-        add_round_key_fhe(&mut state_ck, &xk_ck[..2 * BLOCKSIZE], &sk);
-        print_hex_rdx_fhe("ik_sch", 0, &state_ck, &ck);
+pub fn decrypt_block_fhe(
+    input: &[u8; BLOCKSIZE],
+    xk: &[u8],
+    output: &mut [u8; BLOCKSIZE],
+    iter: usize,
+) -> Result<Metrics, AesFheError> {
+    let (ck, sk) = gen_rdx_keys();
 
-        println!("decrypt_block_fhe         {:.2?}", start.elapsed());
+    let mut state_ck = enc_state_fhe(input, &ck);
+    let xk_ck = enc_round_keys_fhe(xk, &ck)?;
+
+    let mut metrics = Metrics::new();
+    for _ in 0..iter {
+        let (out, m) = decrypt_state_with_fhe(
+            &state_ck,
+            &xk_ck,
+            &SboxCircuit,
+            InvMixColumns::default(),
+            &sk,
+        )?;
+        state_ck = out;
+        metrics.merge(&m);
     }
-    let elapsed = tot.elapsed();
-    println!("AES of #{iter} outputs computed in: {elapsed:?}");
 
-    let output_vec = dec_rdx_vec(&state_ck, &ck);
-    output.copy_from_slice(&output_vec);
+    *output = state_ck.decrypt(&ck);
+
+    Ok(metrics)
 }
@@ -0,0 +1,130 @@
+use crate::aes128_keyschedule::{BLOCKSIZE, KeySize};
+use crate::aes128_tables::{
+    GMUL2, GMUL3, GMUL9, GMULB, GMULD, GMULE, INV_SBOX, INV_SHIFT_ROWS, SBOX, SHIFT_ROWS,
+    permute_blocks,
+};
+
+// the plaintext cipher, step for step as aes128_rdx_fhe, to check the FHE rounds against
+
+// called after every step with the round, the step name and the state
+pub type RefHook<'a> = dyn FnMut(usize, &str, &[u8; BLOCKSIZE]) + 'a;
+
+#[inline]
+pub fn add_round_key(state: &mut [u8; BLOCKSIZE], rkey: &[u8]) {
+    state.iter_mut().zip(rkey).for_each(|(b, k)| *b ^= k);
+}
+
+#[inline]
+pub fn sub_bytes(state: &mut [u8; BLOCKSIZE]) {
+    state.iter_mut().for_each(|b| *b = SBOX[*b as usize]);
+}
+
+#[inline]
+pub fn inv_sub_bytes(state: &mut [u8; BLOCKSIZE]) {
+    state.iter_mut().for_each(|b| *b = INV_SBOX[*b as usize]);
+}
+
+#[inline]
+pub fn shift_rows(state: &mut [u8; BLOCKSIZE]) {
+    permute_blocks(state, &SHIFT_ROWS);
+}
+
+#[inline]
+pub fn inv_shift_rows(state: &mut [u8; BLOCKSIZE]) {
+    permute_blocks(state, &INV_SHIFT_ROWS);
+}
+
+#[inline]
+pub fn mix_columns(state: &mut [u8; BLOCKSIZE]) {
+    let id = core::array::from_fn(|i| i as u8);
+    columns(state, [&GMUL2, &GMUL3, &id, &id]);
+}
+
+#[inline]
+pub fn inv_mix_columns(state: &mut [u8; BLOCKSIZE]) {
+    columns(state, [&GMULE, &GMULB, &GMULD, &GMUL9]);
+}
+
+// tbls is the first row of the matrix, row i is rotated right i times
+fn columns(state: &mut [u8; BLOCKSIZE], tbls: [&[u8; 256]; 4]) {
+    for col in state.chunks_exact_mut(4) {
+        let out: [u8; 4] = core::array::from_fn(|i| {
+            (0..4).fold(0, |acc, j| acc ^ tbls[(j + 4 - i) % 4][col[j] as usize])
+        });
+        col.copy_from_slice(&out);
+    }
+}
+
+#[inline]
+fn round_key(xk: &[u8], round: usize) -> &[u8] {
+    &xk[round * BLOCKSIZE..(round + 1) * BLOCKSIZE]
+}
+
+fn rounds(xk: &[u8]) -> usize {
+    KeySize::from_round_key_size(xk.len())
+        .expect("round keys must be 176, 208 or 240 bytes")
+        .rounds()
+}
+
+pub fn encrypt_state(input: &[u8; BLOCKSIZE], xk: &[u8]) -> [u8; BLOCKSIZE] {
+    encrypt_state_with(input, xk, &mut |_, _, _| {})
+}
+
+pub fn encrypt_state_with(
+    input: &[u8; BLOCKSIZE],
+    xk: &[u8],
+    hook: &mut RefHook,
+) -> [u8; BLOCKSIZE] {
+    let rounds = rounds(xk);
+    let mut state = *input;
+
+    add_round_key(&mut state, round_key(xk, 0));
+    hook(0, "add_round_key", &state);
+
+    for round in 1..=rounds {
+        sub_bytes(&mut state);
+        hook(round, "sub_bytes", &state);
+        shift_rows(&mut state);
+        hook(round, "shift_rows", &state);
+        if round < rounds {
+            mix_columns(&mut state);
+            hook(round, "mix_columns", &state);
+        }
+        add_round_key(&mut state, round_key(xk, round));
+        hook(round, "add_round_key", &state);
+    }
+
+    state
+}
+
+pub fn decrypt_state(input: &[u8; BLOCKSIZE], xk: &[u8]) -> [u8; BLOCKSIZE] {
+    decrypt_state_with(input, xk, &mut |_, _, _| {})
+}
+
+// rounds are numbered in the order they run, as in decrypt_state_with_fhe
+pub fn decrypt_state_with(
+    input: &[u8; BLOCKSIZE],
+    xk: &[u8],
+    hook: &mut RefHook,
+) -> [u8; BLOCKSIZE] {
+    let rounds = rounds(xk);
+    let mut state = *input;
+
+    add_round_key(&mut state, round_key(xk, rounds));
+    hook(0, "add_round_key", &state);
+
+    for r in 1..=rounds {
+        inv_shift_rows(&mut state);
+        hook(r, "inv_shift_rows", &state);
+        inv_sub_bytes(&mut state);
+        hook(r, "inv_sub_bytes", &state);
+        add_round_key(&mut state, round_key(xk, rounds - r));
+        hook(r, "add_round_key", &state);
+        if r < rounds {
+            inv_mix_columns(&mut state);
+            hook(r, "inv_mix_columns", &state);
+        }
+    }
+
+    state
+}
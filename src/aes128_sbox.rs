@@ -0,0 +1,234 @@
+use crate::aes_fhe::num_blocks;
+use crate::aes128_bool_circ::{PosVals, sbox_idx, sbox_inv_idx};
+use crate::aes128_tables::{INV_SBOX, SBOX, gen_tbl};
+
+use tfhe::MatchValues;
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::integer::prelude::ServerKeyDefaultCMux;
+use tfhe::integer::{IntegerCiphertext, ServerKey};
+use tfhe::shortint::Ciphertext;
+
+use std::fmt;
+use std::str::FromStr;
+
+use rayon::prelude::*;
+
+pub trait SboxBackend: Sync {
+    fn sbox(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext>;
+
+    fn sbox_inv(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext>;
+}
+
+// Boyar-Peralta circuit over the 8 bits of the byte
+pub struct SboxCircuit;
+
+impl SboxBackend for SboxCircuit {
+    #[inline]
+    fn sbox(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        sbox_idx(idx, pos_vals, sk)
+    }
+
+    #[inline]
+    fn sbox_inv(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        sbox_inv_idx(idx, pos_vals, sk)
+    }
+}
+
+// one lookup over all 256 values of the byte
+pub struct SboxMatchValues {
+    sbox: MatchValues<u8>,
+    sbox_inv: MatchValues<u8>,
+}
+
+impl SboxMatchValues {
+    pub fn new() -> Self {
+        SboxMatchValues {
+            sbox: gen_tbl(&SBOX),
+            sbox_inv: gen_tbl(&INV_SBOX),
+        }
+    }
+}
+
+impl Default for SboxMatchValues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SboxBackend for SboxMatchValues {
+    #[inline]
+    fn sbox(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        _pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        let (out, _) = sk.unchecked_match_value_parallelized(idx, &self.sbox);
+        extend_to_byte(out, sk)
+    }
+
+    #[inline]
+    fn sbox_inv(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        _pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        let (out, _) = sk.unchecked_match_value_parallelized(idx, &self.sbox_inv);
+        extend_to_byte(out, sk)
+    }
+}
+
+// 16 lookups over the low nibble, one per row of the table, the high nibble
+// selects the row: 16 x 16 selectors on half the blocks instead of 256 on all
+pub struct SboxNibbleLut {
+    sbox: Vec<MatchValues<u8>>,
+    sbox_inv: Vec<MatchValues<u8>>,
+}
+
+impl SboxNibbleLut {
+    pub fn new() -> Self {
+        SboxNibbleLut {
+            sbox: nibble_tbls(&SBOX),
+            sbox_inv: nibble_tbls(&INV_SBOX),
+        }
+    }
+}
+
+impl Default for SboxNibbleLut {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SboxBackend for SboxNibbleLut {
+    #[inline]
+    fn sbox(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        nibble_lut(idx, &self.sbox, pos_vals, sk)
+    }
+
+    #[inline]
+    fn sbox_inv(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        pos_vals: &PosVals,
+        sk: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        nibble_lut(idx, &self.sbox_inv, pos_vals, sk)
+    }
+}
+
+fn nibble_tbls(tbl: &[u8; 256]) -> Vec<MatchValues<u8>> {
+    (0..16)
+        .map(|h| MatchValues::new((0..16).map(|l| (l as u8, tbl[16 * h + l])).collect()).unwrap())
+        .collect()
+}
+
+#[inline]
+fn nibble_lut(
+    idx: &BaseRadixCiphertext<Ciphertext>,
+    tbls: &[MatchValues<u8>],
+    pos_vals: &PosVals,
+    sk: &ServerKey,
+) -> BaseRadixCiphertext<Ciphertext> {
+    let half = idx.blocks().len() / 2;
+    let lo = BaseRadixCiphertext::from(idx.blocks()[..half].to_vec());
+    let hi = BaseRadixCiphertext::from(idx.blocks()[half..].to_vec());
+    let v_0 = pos_vals.get_v_0();
+
+    let rows: Vec<BaseRadixCiphertext<Ciphertext>> = tbls
+        .par_iter()
+        .enumerate()
+        .map(|(h, tbl)| {
+            let ((row, _), sel) = rayon::join(
+                || sk.unchecked_match_value_parallelized(&lo, tbl),
+                || sk.scalar_eq_parallelized(&hi, h as u8),
+            );
+            sk.if_then_else_parallelized(&sel, &extend_to_byte(row, sk), v_0)
+        })
+        .collect();
+
+    // only the selected row is non zero
+    rows.into_iter()
+        .reduce(|a, b| sk.unchecked_bitxor(&a, &b))
+        .unwrap()
+}
+
+#[inline]
+fn extend_to_byte(
+    ct: BaseRadixCiphertext<Ciphertext>,
+    sk: &ServerKey,
+) -> BaseRadixCiphertext<Ciphertext> {
+    let missing = num_blocks(sk).saturating_sub(ct.blocks().len());
+    sk.extend_radix_with_trivial_zero_blocks_msb(&ct, missing)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SboxKind {
+    #[default]
+    BoolCircuit,
+    MatchValues,
+    NibbleLut,
+}
+
+impl SboxKind {
+    pub fn backend(self) -> Box<dyn SboxBackend> {
+        match self {
+            SboxKind::BoolCircuit => Box::new(SboxCircuit),
+            SboxKind::MatchValues => Box::new(SboxMatchValues::new()),
+            SboxKind::NibbleLut => Box::new(SboxNibbleLut::new()),
+        }
+    }
+}
+
+impl FromStr for SboxKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bool-circuit" => Ok(SboxKind::BoolCircuit),
+            "match-values" => Ok(SboxKind::MatchValues),
+            "nibble-lut" => Ok(SboxKind::NibbleLut),
+            _ => Err(format!(
+                "unknown S-box backend {}, expected bool-circuit, match-values or nibble-lut",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SboxKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SboxKind::BoolCircuit => "bool-circuit",
+            SboxKind::MatchValues => "match-values",
+            SboxKind::NibbleLut => "nibble-lut",
+        };
+        write!(f, "{}", name)
+    }
+}
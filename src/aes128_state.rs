@@ -0,0 +1,223 @@
+use crate::aes_fhe::{dec_rdx_vec, enc_rdx_vec, load_bin, num_blocks, save_bin};
+use crate::aes128_keyschedule::{BLOCKSIZE, KeySize};
+use crate::error::AesFheError;
+
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::integer::{RadixClientKey, ServerKey};
+use tfhe::shortint::Ciphertext;
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+// one encrypted AES state, byte 4 * col + row as in the README layout table.
+// serialized as a plain byte vector, so a state file is also a save_rdx_vec file
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(
+    try_from = "Vec<BaseRadixCiphertext<Ciphertext>>",
+    into = "Vec<BaseRadixCiphertext<Ciphertext>>"
+)]
+pub struct EncryptedState {
+    bytes: Vec<BaseRadixCiphertext<Ciphertext>>,
+}
+
+impl EncryptedState {
+    pub fn new(bytes: Vec<BaseRadixCiphertext<Ciphertext>>) -> Result<Self, AesFheError> {
+        if bytes.len() != BLOCKSIZE {
+            return Err(AesFheError::StateLength {
+                expected: BLOCKSIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        Ok(EncryptedState { bytes })
+    }
+
+    pub fn encrypt(state: &[u8; BLOCKSIZE], ck: &RadixClientKey) -> Self {
+        EncryptedState {
+            bytes: enc_rdx_vec(state, ck),
+        }
+    }
+
+    // a known state, e.g. the all zero block of CMAC, without the client key
+    pub fn trivial(state: &[u8; BLOCKSIZE], sk: &ServerKey) -> Self {
+        EncryptedState {
+            bytes: state
+                .iter()
+                .map(|&byte| sk.create_trivial_radix(byte, num_blocks(sk)))
+                .collect(),
+        }
+    }
+
+    pub fn decrypt(&self, ck: &RadixClientKey) -> [u8; BLOCKSIZE] {
+        let mut out = [0u8; BLOCKSIZE];
+        out.copy_from_slice(&dec_rdx_vec(&self.bytes, ck));
+
+        out
+    }
+
+    #[inline]
+    pub fn byte(&self, row: usize, col: usize) -> &BaseRadixCiphertext<Ciphertext> {
+        assert!(row < 4 && col < 4);
+        &self.bytes[4 * col + row]
+    }
+
+    #[inline]
+    pub fn column(&self, col: usize) -> &[BaseRadixCiphertext<Ciphertext>] {
+        &self.bytes[4 * col..4 * (col + 1)]
+    }
+
+    #[inline]
+    pub fn into_vec(self) -> Vec<BaseRadixCiphertext<Ciphertext>> {
+        self.bytes
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AesFheError> {
+        save_bin(path, &self.bytes)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AesFheError> {
+        load_bin(path)
+    }
+}
+
+// the bytes can be changed in place but never added or removed
+impl Deref for EncryptedState {
+    type Target = [BaseRadixCiphertext<Ciphertext>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl DerefMut for EncryptedState {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bytes
+    }
+}
+
+// the ciphertexts are too large to print, only their shape is shown
+impl fmt::Debug for EncryptedState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedState")
+            .field("bytes", &self.bytes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TryFrom<Vec<BaseRadixCiphertext<Ciphertext>>> for EncryptedState {
+    type Error = AesFheError;
+
+    fn try_from(bytes: Vec<BaseRadixCiphertext<Ciphertext>>) -> Result<Self, Self::Error> {
+        EncryptedState::new(bytes)
+    }
+}
+
+impl From<EncryptedState> for Vec<BaseRadixCiphertext<Ciphertext>> {
+    fn from(state: EncryptedState) -> Self {
+        state.bytes
+    }
+}
+
+// the expanded key, rounds + 1 round keys of one state each: 11 for AES-128,
+// 13 for AES-192 and 15 for AES-256
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(
+    try_from = "Vec<BaseRadixCiphertext<Ciphertext>>",
+    into = "Vec<BaseRadixCiphertext<Ciphertext>>"
+)]
+pub struct EncryptedKeySchedule {
+    key_size: KeySize,
+    round_keys: Vec<BaseRadixCiphertext<Ciphertext>>,
+}
+
+impl EncryptedKeySchedule {
+    // the round keys back to back, as key_expansion_fhe builds them
+    pub fn new(round_keys: Vec<BaseRadixCiphertext<Ciphertext>>) -> Result<Self, AesFheError> {
+        let key_size = KeySize::from_round_key_size(round_keys.len())
+            .ok_or(AesFheError::KeyLength(round_keys.len()))?;
+
+        Ok(EncryptedKeySchedule {
+            key_size,
+            round_keys,
+        })
+    }
+
+    pub fn encrypt(xk: &[u8], ck: &RadixClientKey) -> Result<Self, AesFheError> {
+        let key_size =
+            KeySize::from_round_key_size(xk.len()).ok_or(AesFheError::KeyLength(xk.len()))?;
+
+        Ok(EncryptedKeySchedule {
+            key_size,
+            round_keys: enc_rdx_vec(xk, ck),
+        })
+    }
+
+    pub fn decrypt(&self, ck: &RadixClientKey) -> Vec<u8> {
+        dec_rdx_vec(&self.round_keys, ck)
+    }
+
+    #[inline]
+    pub fn key_size(&self) -> KeySize {
+        self.key_size
+    }
+
+    #[inline]
+    pub fn rounds(&self) -> usize {
+        self.key_size.rounds()
+    }
+
+    // round key i of 0..=rounds
+    #[inline]
+    pub fn round_key(&self, i: usize) -> &[BaseRadixCiphertext<Ciphertext>] {
+        assert!(i <= self.rounds(), "round key {} of {}", i, self.rounds());
+        &self.round_keys[i * BLOCKSIZE..(i + 1) * BLOCKSIZE]
+    }
+
+    #[inline]
+    pub fn into_vec(self) -> Vec<BaseRadixCiphertext<Ciphertext>> {
+        self.round_keys
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AesFheError> {
+        save_bin(path, &self.round_keys)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AesFheError> {
+        load_bin(path)
+    }
+}
+
+// read only, the round keys are never changed once expanded
+impl Deref for EncryptedKeySchedule {
+    type Target = [BaseRadixCiphertext<Ciphertext>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.round_keys
+    }
+}
+
+impl fmt::Debug for EncryptedKeySchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedKeySchedule")
+            .field("key_size", &self.key_size)
+            .field("round_keys", &(self.rounds() + 1))
+            .finish_non_exhaustive()
+    }
+}
+
+impl TryFrom<Vec<BaseRadixCiphertext<Ciphertext>>> for EncryptedKeySchedule {
+    type Error = AesFheError;
+
+    fn try_from(round_keys: Vec<BaseRadixCiphertext<Ciphertext>>) -> Result<Self, Self::Error> {
+        EncryptedKeySchedule::new(round_keys)
+    }
+}
+
+impl From<EncryptedKeySchedule> for Vec<BaseRadixCiphertext<Ciphertext>> {
+    fn from(xk: EncryptedKeySchedule) -> Self {
+        xk.round_keys
+    }
+}
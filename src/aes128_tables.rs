@@ -1,12 +1,5 @@
 use tfhe::MatchValues;
 
-use std::time::Instant;
-
-pub const KEYSIZE: usize = 16;
-pub const BLOCKSIZE: usize = 16;
-pub const ROUNDS: usize = 10;
-pub const ROUNDKEYSIZE: usize = BLOCKSIZE * (ROUNDS + 1);
-
 pub const SBOX: [u8; 256] = [
     0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
     0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
@@ -26,6 +19,8 @@ pub const SBOX: [u8; 256] = [
     0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
 ];
 
+pub const INV_SBOX: [u8; 256] = invert_table(&SBOX);
+
 pub const GMUL2: [u8; 256] = generate_gmul_table(0x02);
 pub const GMUL3: [u8; 256] = generate_gmul_table(0x03);
 
@@ -34,15 +29,36 @@ pub const GMULB: [u8; 256] = generate_gmul_table(0x0B);
 pub const GMULD: [u8; 256] = generate_gmul_table(0x0D);
 pub const GMULE: [u8; 256] = generate_gmul_table(0x0E);
 
-pub fn gen_tbl(tbl: &[u8; 256]) -> MatchValues<u8> {
-    let start = Instant::now();
+// byte i of the state comes from byte SHIFT_ROWS[i]
+pub const SHIFT_ROWS: [usize; 16] = [0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12, 1, 6, 11];
+pub const INV_SHIFT_ROWS: [usize; 16] = [0, 13, 10, 7, 4, 1, 14, 11, 8, 5, 2, 15, 12, 9, 6, 3];
 
-    let match_tbl =
-        MatchValues::new(tbl.iter().enumerate().map(|(i, &v)| (i as u8, v)).collect()).unwrap();
+// applies a byte permutation such as SHIFT_ROWS to every block of the state,
+// whatever a byte is: u8, radix or bits
+#[inline]
+pub fn permute_blocks<T: Clone>(state: &mut [T], perm: &[usize; 16]) {
+    for block in state.chunks_exact_mut(perm.len()) {
+        let tmp = block.to_vec();
 
-    println!("gen match tbl time  {:.2?}", start.elapsed());
+        for (i, &j) in perm.iter().enumerate() {
+            block[i] = tmp[j].clone();
+        }
+    }
+}
 
-    match_tbl
+pub fn gen_tbl(tbl: &[u8; 256]) -> MatchValues<u8> {
+    MatchValues::new(tbl.iter().enumerate().map(|(i, &v)| (i as u8, v)).collect()).unwrap()
+}
+
+const fn invert_table(tbl: &[u8; 256]) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[tbl[i] as usize] = i as u8;
+        i += 1;
+    }
+
+    table
 }
 
 const fn generate_gmul_table(multiplier: u8) -> [u8; 256] {
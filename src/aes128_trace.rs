@@ -0,0 +1,179 @@
+use crate::aes_fhe::{dec_rdx_vec, hex};
+use crate::aes128_keyschedule::BLOCKSIZE;
+use crate::aes128_rdx_fhe::{InvMixColumns, decrypt_rounds_fhe, encrypt_rounds_fhe};
+use crate::aes128_ref::{decrypt_state_with, encrypt_state_with};
+use crate::aes128_sbox::SboxBackend;
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
+
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::integer::{RadixClientKey, ServerKey};
+use tfhe::shortint::Ciphertext;
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceStep {
+    pub round: usize,
+    pub step: String,
+    pub state: [u8; BLOCKSIZE],
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<16}[{:>2}] {}",
+            self.step,
+            self.round,
+            hex(&self.state)
+        )
+    }
+}
+
+pub type Observer<'a> = Box<dyn FnMut(&TraceStep) + 'a>;
+
+// decrypts the state after every step, the evaluator must hold the client key;
+// nothing is printed, an observer can print the steps as they come
+pub struct Tracer<'a> {
+    ck: &'a RadixClientKey,
+    observer: Option<Observer<'a>>,
+    steps: Vec<TraceStep>,
+}
+
+impl<'a> Tracer<'a> {
+    pub fn new(ck: &'a RadixClientKey) -> Self {
+        Tracer {
+            ck,
+            observer: None,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn with_observer(mut self, observer: impl FnMut(&TraceStep) + 'a) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    #[inline]
+    pub fn steps(&self) -> &[TraceStep] {
+        &self.steps
+    }
+
+    fn observe(&mut self, round: usize, step: &str, state: &[BaseRadixCiphertext<Ciphertext>]) {
+        let step = TraceStep {
+            round,
+            step: step.to_string(),
+            state: dec_rdx_vec(state, self.ck).try_into().unwrap(),
+        };
+
+        if let Some(observer) = self.observer.as_mut() {
+            observer(&step);
+        }
+
+        self.steps.push(step);
+    }
+}
+
+pub fn encrypt_state_traced_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    tracer: &mut Tracer,
+    sk: &ServerKey,
+) -> Result<(EncryptedState, Metrics), AesFheError> {
+    let (state_ck, metrics) = encrypt_rounds_fhe(
+        state,
+        xk,
+        sbox,
+        &mut |round, step, s| tracer.observe(round, step, s),
+        sk,
+    )?;
+
+    Ok((EncryptedState::new(state_ck)?, metrics))
+}
+
+pub fn decrypt_state_traced_fhe(
+    state: &EncryptedState,
+    xk: &EncryptedKeySchedule,
+    sbox: &dyn SboxBackend,
+    inv_mix: InvMixColumns,
+    tracer: &mut Tracer,
+    sk: &ServerKey,
+) -> Result<(EncryptedState, Metrics), AesFheError> {
+    let (state_ck, metrics) = decrypt_rounds_fhe(
+        state,
+        xk,
+        sbox,
+        inv_mix,
+        &mut |round, step, s| tracer.observe(round, step, s),
+        sk,
+    )?;
+
+    Ok((EncryptedState::new(state_ck)?, metrics))
+}
+
+// the plaintext cipher with the same step names and round numbers as the FHE rounds
+pub fn encrypt_reference(input: &[u8; BLOCKSIZE], xk: &[u8]) -> Vec<TraceStep> {
+    let mut steps = Vec::new();
+    encrypt_state_with(input, xk, &mut |round, step, state| {
+        steps.push(TraceStep {
+            round,
+            step: step.to_string(),
+            state: *state,
+        })
+    });
+
+    steps
+}
+
+pub fn decrypt_reference(input: &[u8; BLOCKSIZE], xk: &[u8]) -> Vec<TraceStep> {
+    let mut steps = Vec::new();
+    decrypt_state_with(input, xk, &mut |round, step, state| {
+        steps.push(TraceStep {
+            round,
+            step: step.to_string(),
+            state: *state,
+        })
+    });
+
+    steps
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub round: usize,
+    pub step: String,
+    pub expected: [u8; BLOCKSIZE],
+    // none when the trace stopped early
+    pub actual: Option<[u8; BLOCKSIZE]>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actual = self.actual.map_or("nothing".to_string(), |s| hex(&s));
+        write!(
+            f,
+            "round {} {}: expected {} got {}",
+            self.round,
+            self.step,
+            hex(&self.expected),
+            actual
+        )
+    }
+}
+
+pub fn first_divergence(trace: &[TraceStep], reference: &[TraceStep]) -> Option<Divergence> {
+    reference.iter().enumerate().find_map(|(i, exp)| {
+        let act = trace.get(i);
+        let same = act.is_some_and(|a| a.step == exp.step && a.state == exp.state);
+
+        (!same).then(|| Divergence {
+            round: exp.round,
+            step: exp.step.clone(),
+            expected: exp.state,
+            actual: act.map(|a| a.state),
+        })
+    })
+}
@@ -0,0 +1,171 @@
+use crate::aes128_bool_circ::{PosVals, get_bool_from_u8, get_u8_from_bool};
+use crate::aes128_keyschedule::BLOCKSIZE;
+use crate::aes128_rdx_fhe::{
+    InvMixColumns, add_round_key_fhe, decrypt_state_with_fhe, encrypt_state_with_fhe,
+};
+use crate::aes128_sbox::{SboxBackend, SboxCircuit};
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use crate::error::AesFheError;
+use crate::metrics::Metrics;
+
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::integer::{BooleanBlock, ServerKey};
+use tfhe::shortint::Ciphertext;
+
+use rayon::prelude::*;
+
+// x^128 + x^7 + x^2 + x + 1, bit 0 is the lsb of byte 0 (little endian)
+const R_XTS: [usize; 4] = [0, 1, 2, 7];
+
+// multiply by alpha, IEEE 1619 5.2
+pub fn xts_mul_alpha_bc(bits: &[BooleanBlock], sk: &ServerKey) -> Vec<BooleanBlock> {
+    assert!(bits.len() == 128);
+
+    let carry = &bits[127];
+    let mut out = Vec::with_capacity(128);
+    out.push(carry.clone());
+    out.extend_from_slice(&bits[..127]);
+
+    out.par_iter_mut().enumerate().for_each(|(i, bit)| {
+        if i != 0 && R_XTS.contains(&i) {
+            *bit = sk.boolean_bitxor(bit, carry);
+        }
+    });
+
+    out
+}
+
+fn xts_tweaks_fhe(
+    xk2: &EncryptedKeySchedule,
+    sector: u128,
+    n_blocks: usize,
+    sbox: &dyn SboxBackend,
+    m: &mut Metrics,
+    sk: &ServerKey,
+) -> Result<Vec<Vec<BaseRadixCiphertext<Ciphertext>>>, AesFheError> {
+    let pos_vals = PosVals::new_trivial(sk);
+
+    let sector_ck = EncryptedState::trivial(&sector.to_le_bytes(), sk);
+    let (t0, t0_metrics) = encrypt_state_with_fhe(&sector_ck, xk2, sbox, sk)?;
+    m.nest(&t0_metrics);
+
+    // the tweak chain is a wire permutation plus four XORs per block
+    Ok(m.time("tweaks", || {
+        let mut bits: Vec<BooleanBlock> = t0
+            .par_iter()
+            .flat_map_iter(|byte| get_bool_from_u8(byte, &pos_vals, sk))
+            .collect();
+
+        let mut tweak_bits = Vec::with_capacity(n_blocks);
+        for _ in 0..n_blocks {
+            let next = xts_mul_alpha_bc(&bits, sk);
+            tweak_bits.push(bits);
+            bits = next;
+        }
+
+        tweak_bits
+            .par_iter()
+            .map(|bits| {
+                bits.par_chunks_exact(8)
+                    .map(|lsb_first| {
+                        let byte: [BooleanBlock; 8] =
+                            core::array::from_fn(|i| lsb_first[i].clone());
+                        get_u8_from_bool(byte, &pos_vals, sk)
+                    })
+                    .collect()
+            })
+            .collect()
+    }))
+}
+
+// None encrypts, the inverse cipher runs with the given InvMixColumns
+fn xts_fhe(
+    xk1: &EncryptedKeySchedule,
+    xk2: &EncryptedKeySchedule,
+    sector: u128,
+    data: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    decrypt: Option<InvMixColumns>,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    // no ciphertext stealing, a sector is a whole number of blocks
+    AesFheError::check_len(data.len(), BLOCKSIZE)?;
+
+    let mut metrics = Metrics::new();
+
+    let out = metrics.run(|m| {
+        let tweaks = xts_tweaks_fhe(xk2, sector, data.len() / BLOCKSIZE, sbox, m, sk)?;
+
+        // the blocks run in parallel, each collects its own steps
+        let blocks: Vec<(EncryptedState, Metrics)> = data
+            .par_chunks_exact(BLOCKSIZE)
+            .zip(tweaks.par_iter())
+            .map(|(block, tweak)| {
+                let mut block_metrics = Metrics::new();
+                let mut state_ck = EncryptedState::new(block.to_vec())?;
+                block_metrics.time("tweak_xor", || add_round_key_fhe(&mut state_ck, tweak, sk))?;
+                let (mut state_ck, round_metrics) = match decrypt {
+                    None => encrypt_state_with_fhe(&state_ck, xk1, sbox, sk)?,
+                    Some(inv_mix) => decrypt_state_with_fhe(&state_ck, xk1, sbox, inv_mix, sk)?,
+                };
+                block_metrics.nest(&round_metrics);
+                block_metrics.time("tweak_xor", || add_round_key_fhe(&mut state_ck, tweak, sk))?;
+                Ok((state_ck, block_metrics))
+            })
+            .collect::<Result<_, AesFheError>>()?;
+
+        let mut out = Vec::with_capacity(data.len());
+        for (state_ck, block_metrics) in blocks {
+            m.nest(&block_metrics);
+            out.extend(state_ck.into_vec());
+        }
+
+        Ok::<_, AesFheError>(out)
+    })?;
+
+    Ok((out, metrics))
+}
+
+pub fn xts_encrypt_fhe(
+    xk1: &EncryptedKeySchedule,
+    xk2: &EncryptedKeySchedule,
+    sector: u128,
+    data: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    Ok(xts_encrypt_with_fhe(xk1, xk2, sector, data, &SboxCircuit, sk)?.0)
+}
+
+pub fn xts_encrypt_with_fhe(
+    xk1: &EncryptedKeySchedule,
+    xk2: &EncryptedKeySchedule,
+    sector: u128,
+    data: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    xts_fhe(xk1, xk2, sector, data, sbox, None, sk)
+}
+
+pub fn xts_decrypt_fhe(
+    xk1: &EncryptedKeySchedule,
+    xk2: &EncryptedKeySchedule,
+    sector: u128,
+    data: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    let inv_mix = InvMixColumns::default();
+    Ok(xts_decrypt_with_fhe(xk1, xk2, sector, data, &SboxCircuit, inv_mix, sk)?.0)
+}
+
+pub fn xts_decrypt_with_fhe(
+    xk1: &EncryptedKeySchedule,
+    xk2: &EncryptedKeySchedule,
+    sector: u128,
+    data: &[BaseRadixCiphertext<Ciphertext>],
+    sbox: &dyn SboxBackend,
+    inv_mix: InvMixColumns,
+    sk: &ServerKey,
+) -> Result<(Vec<BaseRadixCiphertext<Ciphertext>>, Metrics), AesFheError> {
+    xts_fhe(xk1, xk2, sector, data, sbox, Some(inv_mix), sk)
+}
@@ -1,16 +1,196 @@
+use crate::aes128_bool_circ::PosVals;
+use crate::aes128_keyschedule::BLOCKSIZE;
+use crate::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use crate::error::AesFheError;
+
 use tfhe::integer::ciphertext::BaseRadixCiphertext;
-use tfhe::integer::{RadixClientKey, ServerKey, gen_keys_radix};
+use tfhe::integer::{IntegerCiphertext, RadixClientKey, ServerKey, gen_keys_radix};
 use tfhe::shortint::Ciphertext;
-use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2_KS_PBS;
+use tfhe::shortint::ClassicPBSParameters;
+use tfhe::shortint::parameters::v1_8::classic::tuniform::p_fail_2_minus_128::ks_pbs::{
+    V1_8_PARAM_MESSAGE_1_CARRY_1_KS_PBS_TUNIFORM_2M128,
+    V1_8_PARAM_MESSAGE_2_CARRY_2_KS_PBS_TUNIFORM_2M128,
+    V1_8_PARAM_MESSAGE_4_CARRY_4_KS_PBS_TUNIFORM_2M128,
+};
+
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use std::str::FromStr;
+
+// blocks per byte of the default parameter set only, the others follow from
+// FheConfig::num_blocks or num_blocks(sk)
+pub const DEFAULT_NUM_BLOCK: usize = FheConfig::new(ParamSet::Message2Carry2).num_blocks();
+
+// the server key is the largest object, a few hundred MB
+pub const SERIALIZED_SIZE_LIMIT: u64 = 1 << 30;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParamSet {
+    Message1Carry1,
+    #[default]
+    Message2Carry2,
+    Message4Carry4,
+}
+
+impl ParamSet {
+    pub const ALL: [ParamSet; 3] = [
+        ParamSet::Message1Carry1,
+        ParamSet::Message2Carry2,
+        ParamSet::Message4Carry4,
+    ];
+
+    // one tfhe generation and failure probability (2^-128, TUniform noise), so
+    // the sets differ only in their message and carry bits; taken from the
+    // versioned v1_8 module, not current_params, so that a newer tfhe neither
+    // breaks the build nor changes the parameters of saved keys
+    pub fn params(self) -> ClassicPBSParameters {
+        match self {
+            ParamSet::Message1Carry1 => V1_8_PARAM_MESSAGE_1_CARRY_1_KS_PBS_TUNIFORM_2M128,
+            ParamSet::Message2Carry2 => V1_8_PARAM_MESSAGE_2_CARRY_2_KS_PBS_TUNIFORM_2M128,
+            ParamSet::Message4Carry4 => V1_8_PARAM_MESSAGE_4_CARRY_4_KS_PBS_TUNIFORM_2M128,
+        }
+    }
+
+    pub const fn message_bits(self) -> usize {
+        match self {
+            ParamSet::Message1Carry1 => 1,
+            ParamSet::Message2Carry2 => 2,
+            ParamSet::Message4Carry4 => 4,
+        }
+    }
+}
+
+impl FromStr for ParamSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1_1" => Ok(ParamSet::Message1Carry1),
+            "2_2" => Ok(ParamSet::Message2Carry2),
+            "4_4" => Ok(ParamSet::Message4Carry4),
+            _ => Err(format!(
+                "unknown parameter set {}, expected 1_1, 2_2 or 4_4",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ParamSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = self.message_bits();
+        write!(f, "{}_{}", bits, bits)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FheConfig {
+    pub param_set: ParamSet,
+}
+
+impl FheConfig {
+    pub const fn new(param_set: ParamSet) -> Self {
+        FheConfig { param_set }
+    }
+
+    // a byte is split into num_blocks radix blocks of message_bits each, the
+    // same count num_blocks(sk) gives the server for keys of this config
+    #[inline]
+    pub const fn num_blocks(&self) -> usize {
+        8usize.div_ceil(self.param_set.message_bits())
+    }
 
-pub const NUM_BLOCK: usize = 4;
+    // the server key carries the parameters, the client config must agree with it
+    pub fn matches(&self, sk: &ServerKey) -> bool {
+        let params = self.param_set.params();
+        sk.message_modulus() == params.message_modulus && sk.carry_modulus() == params.carry_modulus
+    }
+
+    // the supported parameter set a server key was generated with
+    pub fn of_server_key(sk: &ServerKey) -> Result<Self, AesFheError> {
+        ParamSet::ALL
+            .into_iter()
+            .map(FheConfig::new)
+            .find(|config| config.matches(sk))
+            .ok_or_else(|| {
+                AesFheError::ParamMismatch(format!(
+                    "server key of message modulus {} and carry modulus {} is none of 1_1, 2_2 or 4_4",
+                    sk.message_modulus().0,
+                    sk.carry_modulus().0
+                ))
+            })
+    }
+
+    pub fn check(&self, sk: &ServerKey) -> Result<(), AesFheError> {
+        if !self.matches(sk) {
+            return Err(AesFheError::ParamMismatch(format!(
+                "server key is not {}",
+                self.param_set
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FheConfig {
+    fn default() -> Self {
+        Self::new(ParamSet::default())
+    }
+}
 
 pub fn gen_rdx_keys() -> (RadixClientKey, ServerKey) {
-    let (rdx_ck, rdx_sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCK);
+    gen_rdx_keys_with(&FheConfig::default())
+}
+
+pub fn gen_rdx_keys_with(config: &FheConfig) -> (RadixClientKey, ServerKey) {
+    let (rdx_ck, rdx_sk) = gen_keys_radix(config.param_set.params(), config.num_blocks());
 
     (rdx_ck, rdx_sk)
 }
 
+// radix blocks per byte, the server only has the key to go by
+#[inline]
+pub fn num_blocks(sk: &ServerKey) -> usize {
+    let bits = sk.message_modulus().0.ilog2() as usize;
+    8usize.div_ceil(bits)
+}
+
+// every byte must have the blocks of the server key's parameter set
+pub fn check_params(
+    enc: &[BaseRadixCiphertext<Ciphertext>],
+    sk: &ServerKey,
+) -> Result<(), AesFheError> {
+    let num_blocks = num_blocks(sk);
+
+    for byte in enc {
+        let blocks = byte.blocks();
+        if blocks.len() != num_blocks {
+            return Err(AesFheError::ParamMismatch(format!(
+                "byte of {} blocks, the server key expects {}",
+                blocks.len(),
+                num_blocks
+            )));
+        }
+        if blocks
+            .iter()
+            .any(|b| b.message_modulus != sk.message_modulus())
+        {
+            return Err(AesFheError::ParamMismatch(
+                "message modulus differs from the server key".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 pub fn enc_rdx_vec(plain: &[u8], ck: &RadixClientKey) -> Vec<BaseRadixCiphertext<Ciphertext>> {
     let enc_ck: Vec<_> = plain
         .iter()
@@ -26,19 +206,141 @@ pub fn dec_rdx_vec(enc: &[BaseRadixCiphertext<Ciphertext>], ck: &RadixClientKey)
     plain
 }
 
-pub fn print_hex_rdx_fhe(
+pub fn enc_state_fhe(state: &[u8; BLOCKSIZE], ck: &RadixClientKey) -> EncryptedState {
+    EncryptedState::encrypt(state, ck)
+}
+
+pub fn enc_round_keys_fhe(
+    xk: &[u8],
+    ck: &RadixClientKey,
+) -> Result<EncryptedKeySchedule, AesFheError> {
+    EncryptedKeySchedule::encrypt(xk, ck)
+}
+
+pub fn dec_state_fhe(
+    state: &[BaseRadixCiphertext<Ciphertext>],
+    ck: &RadixClientKey,
+) -> Result<[u8; BLOCKSIZE], AesFheError> {
+    if state.len() != BLOCKSIZE {
+        return Err(AesFheError::StateLength {
+            expected: BLOCKSIZE,
+            actual: state.len(),
+        });
+    }
+
+    let mut out = [0u8; BLOCKSIZE];
+    out.copy_from_slice(&dec_rdx_vec(state, ck));
+
+    Ok(out)
+}
+
+pub fn pkcs7_pad(data: &[u8]) -> Vec<u8> {
+    let pad = BLOCKSIZE - data.len() % BLOCKSIZE;
+
+    let mut out = data.to_vec();
+    out.resize(data.len() + pad, pad as u8);
+
+    out
+}
+
+pub fn pkcs7_unpad(data: &[u8]) -> Option<&[u8]> {
+    if data.is_empty() || !data.len().is_multiple_of(BLOCKSIZE) {
+        return None;
+    }
+
+    let pad = *data.last()? as usize;
+    if pad == 0 || pad > BLOCKSIZE {
+        return None;
+    }
+
+    let (msg, padding) = data.split_at(data.len() - pad);
+    padding.iter().all(|&b| b as usize == pad).then_some(msg)
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// None for anything but an even number of hex digits, never panics
+pub fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    // all ascii from here, the byte offsets are char boundaries
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+// the caller decides where the line goes
+pub fn fmt_hex_rdx_fhe(
     label: &str,
     idx: usize,
     enc_data: &[BaseRadixCiphertext<Ciphertext>],
     ck: &RadixClientKey,
-) {
-    let state = dec_rdx_vec(enc_data, ck);
+) -> String {
+    format!("{}  [{}] {}", label, idx, hex(&dec_rdx_vec(enc_data, ck)))
+}
 
-    let hex_output: String = state
-        .iter()
-        .map(|byte| format!("{:02x}", byte))
-        .collect::<Vec<String>>()
-        .join("");
+#[inline]
+fn bincode_opts() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(SERIALIZED_SIZE_LIMIT)
+}
+
+pub(crate) fn save_bin<T: Serialize>(path: &Path, value: &T) -> Result<(), AesFheError> {
+    let writer = BufWriter::new(File::create(path)?);
+    bincode_opts()
+        .serialize_into(writer, value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(())
+}
+
+pub(crate) fn load_bin<T: DeserializeOwned>(path: &Path) -> Result<T, AesFheError> {
+    let reader = BufReader::new(File::open(path)?);
+    bincode_opts()
+        .deserialize_from(reader)
+        .map_err(AesFheError::Deserialize)
+}
+
+pub fn save_client_key(path: &Path, ck: &RadixClientKey) -> Result<(), AesFheError> {
+    save_bin(path, ck)
+}
+
+pub fn load_client_key(path: &Path) -> Result<RadixClientKey, AesFheError> {
+    load_bin(path)
+}
+
+pub fn save_server_key(path: &Path, sk: &ServerKey) -> Result<(), AesFheError> {
+    save_bin(path, sk)
+}
+
+// a key of any other parameter set is rejected here, not in the first bootstrap
+pub fn load_server_key(path: &Path) -> Result<ServerKey, AesFheError> {
+    let sk = load_bin(path)?;
+    FheConfig::of_server_key(&sk)?;
+
+    Ok(sk)
+}
+
+pub fn save_pos_vals(path: &Path, pos_vals: &PosVals) -> Result<(), AesFheError> {
+    save_bin(path, pos_vals)
+}
+
+pub fn load_pos_vals(path: &Path) -> Result<PosVals, AesFheError> {
+    load_bin(path)
+}
+
+// encrypted states, round keys and any other byte vector
+pub fn save_rdx_vec(
+    path: &Path,
+    enc: &[BaseRadixCiphertext<Ciphertext>],
+) -> Result<(), AesFheError> {
+    save_bin(path, &enc)
+}
 
-    println!("{}  [{}] {}", label, idx, hex_output);
+pub fn load_rdx_vec(path: &Path) -> Result<Vec<BaseRadixCiphertext<Ciphertext>>, AesFheError> {
+    load_bin(path)
 }
@@ -0,0 +1,547 @@
+use crate::error::AesFheError;
+
+use tfhe::integer::{BooleanBlock, ServerKey};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gate {
+    Xor(usize, usize),
+    And(usize, usize),
+    Not(usize),
+    Const(bool),
+}
+
+impl Gate {
+    #[inline]
+    fn operands(&self) -> [Option<usize>; 2] {
+        match *self {
+            Gate::Xor(a, b) | Gate::And(a, b) => [Some(a), Some(b)],
+            Gate::Not(a) => [Some(a), None],
+            Gate::Const(_) => [None, None],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Xor,
+    And,
+    Not,
+    Const(bool),
+    Copy,
+}
+
+// a named wire driven by an operation on named wires
+struct Instr {
+    name: String,
+    op: Op,
+    args: Vec<String>,
+}
+
+// wires 0..num_inputs are the inputs, gate i drives wire num_inputs + i
+#[derive(Clone, Debug)]
+pub struct BoolCircuit {
+    num_inputs: usize,
+    gates: Vec<Gate>,
+    levels: Vec<Vec<usize>>,
+    outputs: Vec<usize>,
+    input_sizes: Vec<usize>,
+    output_sizes: Vec<usize>,
+}
+
+impl BoolCircuit {
+    // gates must be in topological order: a gate only reads inputs and earlier gates
+    pub fn new(
+        num_inputs: usize,
+        gates: Vec<Gate>,
+        outputs: Vec<usize>,
+    ) -> Result<Self, AesFheError> {
+        let num_wires = num_inputs + gates.len();
+        let mut depth = vec![0usize; num_wires];
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+
+        for (i, gate) in gates.iter().enumerate() {
+            let wire = num_inputs + i;
+            let operands = gate.operands();
+            if let Some(a) = operands.iter().flatten().find(|&&a| a >= wire) {
+                return Err(AesFheError::InvalidCircuit(format!(
+                    "gate {} reads wire {}, not driven before it",
+                    i, a
+                )));
+            }
+
+            depth[wire] = 1 + operands
+                .iter()
+                .flatten()
+                .map(|&a| depth[a])
+                .max()
+                .unwrap_or(0);
+            if levels.len() < depth[wire] {
+                levels.push(Vec::new());
+            }
+            levels[depth[wire] - 1].push(i);
+        }
+
+        if let Some(w) = outputs.iter().find(|&&w| w >= num_wires) {
+            return Err(AesFheError::MissingWire(format!(
+                "output {} of {} wires",
+                w, num_wires
+            )));
+        }
+
+        Ok(BoolCircuit {
+            num_inputs,
+            gates,
+            levels,
+            input_sizes: vec![num_inputs],
+            output_sizes: vec![outputs.len()],
+            outputs,
+        })
+    }
+
+    // "a = b ^ c", "a = b & c" and "a = b !" instructions, in any order
+    pub fn from_instr(
+        instr: &[&str],
+        inputs: &[String],
+        outputs: &[String],
+    ) -> Result<Self, AesFheError> {
+        let parsed = instr
+            .iter()
+            .map(|st| parse_instr_line(st))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        compile(&parsed, inputs, outputs)
+    }
+
+    // one instruction per line, blank lines and lines starting with # are skipped
+    pub fn parse_instr(
+        text: &str,
+        inputs: &[String],
+        outputs: &[String],
+    ) -> Result<Self, AesFheError> {
+        let instr: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+
+        Self::from_instr(&instr, inputs, outputs)
+    }
+
+    // https://nigelsmart.github.io/MPC-Circuits/
+    // header "gates wires", "niv sizes..", "nov sizes..", then one gate per line:
+    // "in out in_wires.. out_wires.. XOR|AND|INV|EQ|EQW|MAND"
+    pub fn parse_bristol(text: &str) -> Result<Self, AesFheError> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        let mut header = |what: &str| -> Result<Vec<usize>, AesFheError> {
+            let line = lines
+                .next()
+                .ok_or(AesFheError::MalformedInstruction(format!(
+                    "missing {} line",
+                    what
+                )))?;
+            line.split_whitespace()
+                .map(|t| {
+                    t.parse::<usize>().map_err(|_| {
+                        AesFheError::MalformedInstruction(format!("{} line: {}", what, line))
+                    })
+                })
+                .collect()
+        };
+
+        let counts = header("gate count")?;
+        let input_line = header("input")?;
+        let output_line = header("output")?;
+
+        let [num_gates, num_wires] = counts[..] else {
+            return Err(AesFheError::MalformedInstruction(
+                "gate count line must hold two numbers".to_string(),
+            ));
+        };
+        let sizes = |line: &[usize], what: &str| -> Result<Vec<usize>, AesFheError> {
+            match line.split_first() {
+                Some((&n, sizes)) if n == sizes.len() => Ok(sizes.to_vec()),
+                _ => Err(AesFheError::MalformedInstruction(format!("{} line", what))),
+            }
+        };
+        let input_sizes = sizes(&input_line, "input")?;
+        let output_sizes = sizes(&output_line, "output")?;
+
+        let num_inputs: usize = input_sizes.iter().sum();
+        let num_outputs: usize = output_sizes.iter().sum();
+        if num_inputs + num_outputs > num_wires {
+            return Err(AesFheError::InvalidCircuit(
+                "more inputs and outputs than wires".to_string(),
+            ));
+        }
+
+        let mut parsed = Vec::with_capacity(num_gates);
+        let mut gate_count = 0;
+
+        for line in lines {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let malformed = || AesFheError::MalformedInstruction(line.to_string());
+
+            let (op, rest) = tokens.split_last().ok_or_else(malformed)?;
+            let nums = rest
+                .iter()
+                .map(|t| t.parse::<usize>().map_err(|_| malformed()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let (n_in, n_out) = match nums[..] {
+                [n_in, n_out, ..] if nums.len() == 2 + n_in + n_out => (n_in, n_out),
+                _ => return Err(malformed()),
+            };
+            let args = &nums[2..2 + n_in];
+            let outs = &nums[2 + n_in..];
+            if let Some(w) = args.iter().chain(outs).find(|&&w| w >= num_wires) {
+                return Err(AesFheError::MissingWire(format!("{} in {}", w, line)));
+            }
+
+            let mut push = |name: usize, op: Op, args: &[usize]| {
+                parsed.push(Instr {
+                    name: name.to_string(),
+                    op,
+                    args: args.iter().map(|a| a.to_string()).collect(),
+                })
+            };
+
+            match (*op, n_in, n_out) {
+                ("XOR", 2, 1) => push(outs[0], Op::Xor, args),
+                ("AND", 2, 1) => push(outs[0], Op::And, args),
+                ("INV", 1, 1) => push(outs[0], Op::Not, args),
+                ("EQW", 1, 1) => push(outs[0], Op::Copy, args),
+                // the input of EQ is the constant itself
+                ("EQ", 1, 1) if args[0] <= 1 => push(outs[0], Op::Const(args[0] == 1), &[]),
+                ("MAND", _, _) if n_in == 2 * n_out => {
+                    for (i, &out) in outs.iter().enumerate() {
+                        push(out, Op::And, &[args[i], args[n_out + i]]);
+                    }
+                }
+                _ => return Err(malformed()),
+            }
+
+            gate_count += 1;
+        }
+
+        if gate_count != num_gates {
+            return Err(AesFheError::InvalidCircuit(format!(
+                "expected {} gates, found {}",
+                num_gates, gate_count
+            )));
+        }
+
+        // the outputs are the last wires
+        let inputs: Vec<String> = (0..num_inputs).map(|w| w.to_string()).collect();
+        let outputs: Vec<String> = (num_wires - num_outputs..num_wires)
+            .map(|w| w.to_string())
+            .collect();
+
+        let mut circuit = compile(&parsed, &inputs, &outputs)?;
+        circuit.input_sizes = input_sizes;
+        circuit.output_sizes = output_sizes;
+
+        Ok(circuit)
+    }
+
+    // Bristol Fashion when the first line holds two numbers, one instruction per line otherwise
+    pub fn load(path: &Path, inputs: &[String], outputs: &[String]) -> Result<Self, AesFheError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+
+        let first = text
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("");
+        let tokens: Vec<&str> = first.split_whitespace().collect();
+
+        if tokens.len() == 2 && tokens.iter().all(|t| t.parse::<usize>().is_ok()) {
+            Self::parse_bristol(&text)
+        } else {
+            Self::parse_instr(&text, inputs, outputs)
+        }
+    }
+
+    // the outputs are copied to the last wires, as Bristol Fashion requires
+    pub fn to_bristol(&self) -> String {
+        let num_wires = self.num_inputs + self.gates.len();
+        let sizes = |sizes: &[usize]| {
+            let mut line = sizes.len().to_string();
+            for size in sizes {
+                line.push_str(&format!(" {}", size));
+            }
+            line
+        };
+
+        let mut text = format!(
+            "{} {}\n{}\n{}\n\n",
+            self.gates.len() + self.outputs.len(),
+            num_wires + self.outputs.len(),
+            sizes(&self.input_sizes),
+            sizes(&self.output_sizes)
+        );
+
+        for (i, gate) in self.gates.iter().enumerate() {
+            let out = self.num_inputs + i;
+            let line = match *gate {
+                Gate::Xor(a, b) => format!("2 1 {} {} {} XOR", a, b, out),
+                Gate::And(a, b) => format!("2 1 {} {} {} AND", a, b, out),
+                Gate::Not(a) => format!("1 1 {} {} INV", a, out),
+                Gate::Const(c) => format!("1 1 {} {} EQ", c as u8, out),
+            };
+            text.push_str(&line);
+            text.push('\n');
+        }
+
+        for (i, &w) in self.outputs.iter().enumerate() {
+            text.push_str(&format!("1 1 {} {} EQW\n", w, num_wires + i));
+        }
+
+        text
+    }
+
+    // the outputs of self feed the inputs of next
+    pub fn compose(&self, next: &BoolCircuit) -> Result<BoolCircuit, AesFheError> {
+        if self.outputs.len() != next.num_inputs {
+            return Err(AesFheError::InvalidCircuit(format!(
+                "{} outputs cannot feed {} inputs",
+                self.outputs.len(),
+                next.num_inputs
+            )));
+        }
+
+        let num_wires = self.num_inputs + self.gates.len();
+        let wire = |w: usize| {
+            if w < next.num_inputs {
+                self.outputs[w]
+            } else {
+                num_wires + w - next.num_inputs
+            }
+        };
+
+        let mut gates = self.gates.clone();
+        gates.extend(next.gates.iter().map(|gate| match *gate {
+            Gate::Xor(a, b) => Gate::Xor(wire(a), wire(b)),
+            Gate::And(a, b) => Gate::And(wire(a), wire(b)),
+            Gate::Not(a) => Gate::Not(wire(a)),
+            Gate::Const(c) => Gate::Const(c),
+        }));
+        let outputs = next.outputs.iter().map(|&w| wire(w)).collect();
+
+        let mut circuit = BoolCircuit::new(self.num_inputs, gates, outputs)?;
+        circuit.input_sizes = self.input_sizes.clone();
+        circuit.output_sizes = next.output_sizes.clone();
+
+        Ok(circuit)
+    }
+
+    #[inline]
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    #[inline]
+    pub fn num_outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    #[inline]
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    #[inline]
+    pub fn input_sizes(&self) -> &[usize] {
+        &self.input_sizes
+    }
+
+    #[inline]
+    pub fn output_sizes(&self) -> &[usize] {
+        &self.output_sizes
+    }
+
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn eval(
+        &self,
+        inp: &[BooleanBlock],
+        sk: &ServerKey,
+    ) -> Result<Vec<BooleanBlock>, AesFheError> {
+        self.check_inputs(inp.len())?;
+
+        let mut wires: Vec<Option<BooleanBlock>> = inp.iter().cloned().map(Some).collect();
+        wires.resize(self.num_inputs + self.gates.len(), None);
+
+        // gates of a level only read earlier levels
+        for level in &self.levels {
+            let values: Vec<BooleanBlock> = level
+                .par_iter()
+                .map(|&i| {
+                    let w = |idx: usize| wires[idx].as_ref().unwrap();
+                    match self.gates[i] {
+                        Gate::Xor(a, b) => sk.boolean_bitxor(w(a), w(b)),
+                        Gate::And(a, b) => sk.boolean_bitand(w(a), w(b)),
+                        Gate::Not(a) => sk.boolean_bitnot(w(a)),
+                        Gate::Const(c) => sk.create_trivial_boolean_block(c),
+                    }
+                })
+                .collect();
+
+            for (&i, value) in level.iter().zip(values) {
+                wires[self.num_inputs + i] = Some(value);
+            }
+        }
+
+        Ok(self
+            .outputs
+            .iter()
+            .map(|&w| wires[w].clone().unwrap())
+            .collect())
+    }
+
+    pub fn eval_plain(&self, inp: &[bool]) -> Result<Vec<bool>, AesFheError> {
+        self.check_inputs(inp.len())?;
+
+        let mut wires = inp.to_vec();
+        for gate in &self.gates {
+            let value = match *gate {
+                Gate::Xor(a, b) => wires[a] ^ wires[b],
+                Gate::And(a, b) => wires[a] & wires[b],
+                Gate::Not(a) => !wires[a],
+                Gate::Const(c) => c,
+            };
+            wires.push(value);
+        }
+
+        Ok(self.outputs.iter().map(|&w| wires[w]).collect())
+    }
+
+    fn check_inputs(&self, len: usize) -> Result<(), AesFheError> {
+        if len != self.num_inputs {
+            return Err(AesFheError::InvalidCircuit(format!(
+                "{} input bits for a circuit of {} inputs",
+                len, self.num_inputs
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_instr_line(st: &str) -> Result<Instr, AesFheError> {
+    let tokens = st.split_whitespace().collect::<Vec<_>>();
+
+    let (name, op, args) = match tokens.as_slice() {
+        [name, "=", a, "^", b] => (name, Op::Xor, vec![a, b]),
+        [name, "=", a, "&", b] => (name, Op::And, vec![a, b]),
+        [name, "=", a, "!"] => (name, Op::Not, vec![a]),
+        _ => return Err(AesFheError::MalformedInstruction(st.to_string())),
+    };
+
+    Ok(Instr {
+        name: name.to_string(),
+        op,
+        args: args.iter().map(|a| a.to_string()).collect(),
+    })
+}
+
+// orders the instructions so that every gate follows its operands
+fn compile(
+    instr: &[Instr],
+    inputs: &[String],
+    outputs: &[String],
+) -> Result<BoolCircuit, AesFheError> {
+    let mut defs: HashMap<&str, usize> = HashMap::with_capacity(instr.len());
+    for (i, ins) in instr.iter().enumerate() {
+        if inputs.contains(&ins.name) || defs.insert(ins.name.as_str(), i).is_some() {
+            return Err(AesFheError::InvalidCircuit(format!(
+                "wire driven twice: {}",
+                ins.name
+            )));
+        }
+    }
+
+    let mut wire: HashMap<&str, usize> = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i))
+        .collect();
+    let mut gates = Vec::with_capacity(instr.len());
+    let mut visiting = vec![false; instr.len()];
+
+    // depth first
+    for root in 0..instr.len() {
+        let mut stack = vec![(root, false)];
+
+        while let Some((i, expanded)) = stack.pop() {
+            let ins = &instr[i];
+            if wire.contains_key(ins.name.as_str()) {
+                continue;
+            }
+
+            if !expanded {
+                if visiting[i] {
+                    return Err(AesFheError::InvalidCircuit(format!(
+                        "cyclic circuit at wire: {}",
+                        ins.name
+                    )));
+                }
+                visiting[i] = true;
+                stack.push((i, true));
+
+                for arg in ins
+                    .args
+                    .iter()
+                    .filter(|arg| !wire.contains_key(arg.as_str()))
+                {
+                    let def = *defs
+                        .get(arg.as_str())
+                        .ok_or(AesFheError::MissingWire(arg.clone()))?;
+                    stack.push((def, false));
+                }
+                continue;
+            }
+
+            let w = |k: usize| wire[ins.args[k].as_str()];
+            let gate = match ins.op {
+                Op::Xor => Gate::Xor(w(0), w(1)),
+                Op::And => Gate::And(w(0), w(1)),
+                Op::Not => Gate::Not(w(0)),
+                Op::Const(c) => Gate::Const(c),
+                // a copy is the operand wire itself
+                Op::Copy => {
+                    wire.insert(ins.name.as_str(), w(0));
+                    continue;
+                }
+            };
+
+            wire.insert(ins.name.as_str(), inputs.len() + gates.len());
+            gates.push(gate);
+        }
+    }
+
+    let outputs = outputs
+        .iter()
+        .map(|name| {
+            wire.get(name.as_str())
+                .copied()
+                .ok_or(AesFheError::MissingWire(name.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    BoolCircuit::new(inputs.len(), gates, outputs)
+}
+
+pub fn wire_names(prefix: &str, n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("{}{}", prefix, i)).collect()
+}
@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum AesFheError {
+    // a state, or a batch of states, of the wrong number of bytes
+    StateLength { expected: usize, actual: usize },
+    // a key or its round keys of none of the AES sizes
+    KeyLength(usize),
+    // a single round key, always one state of 16 bytes
+    RoundKeyLength(usize),
+    // BooleanBlocks that do not make whole bytes
+    BitLength { expected: usize, actual: usize },
+    MalformedInstruction(String),
+    MissingWire(String),
+    // instructions that parse but do not make a circuit: a wire driven twice,
+    // a cycle, or the wrong number of inputs and outputs
+    InvalidCircuit(String),
+    // ciphertexts, keys and config from different parameter sets
+    ParamMismatch(String),
+    // a line or record of a NIST CAVP .rsp file
+    MalformedVector(String),
+    Deserialize(bincode::Error),
+    Io(io::Error),
+}
+
+impl AesFheError {
+    // a batch is any non empty multiple of unit bytes
+    pub fn check_len(actual: usize, unit: usize) -> Result<(), AesFheError> {
+        if actual == 0 || !actual.is_multiple_of(unit) {
+            return Err(AesFheError::StateLength {
+                expected: actual.next_multiple_of(unit).max(unit),
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for AesFheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AesFheError::StateLength { expected, actual } => {
+                write!(f, "state of {} bytes, expected {}", actual, expected)
+            }
+            AesFheError::KeyLength(len) => write!(
+                f,
+                "key of {} bytes, expected 16, 24 or 32 bytes or 176, 208 or 240 of round keys",
+                len
+            ),
+            AesFheError::RoundKeyLength(len) => {
+                write!(f, "round key of {} bytes, expected 16", len)
+            }
+            AesFheError::BitLength { expected, actual } => {
+                write!(f, "{} bits, expected {}", actual, expected)
+            }
+            AesFheError::MalformedInstruction(msg) => write!(f, "malformed instruction: {}", msg),
+            AesFheError::MissingWire(wire) => write!(f, "missing wire: {}", wire),
+            AesFheError::InvalidCircuit(msg) => write!(f, "invalid circuit: {}", msg),
+            AesFheError::ParamMismatch(msg) => write!(f, "parameter mismatch: {}", msg),
+            AesFheError::MalformedVector(msg) => write!(f, "malformed test vector: {}", msg),
+            AesFheError::Deserialize(e) => write!(f, "deserialization failed: {}", e),
+            AesFheError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for AesFheError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AesFheError::Deserialize(e) => Some(e),
+            AesFheError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AesFheError {
+    fn from(e: io::Error) -> Self {
+        AesFheError::Io(e)
+    }
+}
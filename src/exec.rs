@@ -0,0 +1,42 @@
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use std::sync::Arc;
+
+// the rayon pool every FHE step runs on, tfhe's parallel operations included,
+// so the evaluator stays within a CPU budget next to other services; the
+// library functions (encrypt_states_with_fhe, the modes, ...) take no pool
+// argument, they use whichever pool is installed around them
+#[derive(Clone)]
+pub struct ExecContext {
+    pool: Arc<ThreadPool>,
+}
+
+impl ExecContext {
+    // 0 threads lets rayon decide, RAYON_NUM_THREADS or one per CPU
+    pub fn new(threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("aes-fhe-{}", i))
+            .build()?;
+
+        Ok(ExecContext {
+            pool: Arc::new(pool),
+        })
+    }
+
+    #[inline]
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    #[inline]
+    pub fn pool(&self) -> &ThreadPool {
+        &self.pool
+    }
+
+    // every par_iter reached from f, nested or not, uses this pool
+    #[inline]
+    pub fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        self.pool.install(f)
+    }
+}
@@ -1,5 +1,21 @@
+pub mod aes128_bitsliced;
 pub mod aes128_bool_circ;
+pub mod aes128_cipher;
+pub mod aes128_cmac;
+pub mod aes128_gcm;
+pub mod aes128_kat;
 pub mod aes128_keyschedule;
+pub mod aes128_modes;
 pub mod aes128_rdx_fhe;
+pub mod aes128_ref;
+pub mod aes128_sbox;
+pub mod aes128_state;
 pub mod aes128_tables;
+#[cfg(feature = "debug-trace")]
+pub mod aes128_trace;
+pub mod aes128_xts;
 pub mod aes_fhe;
+pub mod bool_circ;
+pub mod error;
+pub mod exec;
+pub mod metrics;
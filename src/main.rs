@@ -0,0 +1,240 @@
+use aes128_rdx_bc_fhe::aes_fhe::{
+    FheConfig, ParamSet, dec_rdx_vec, enc_rdx_vec, enc_round_keys_fhe, gen_rdx_keys_with, hex,
+    load_client_key, load_rdx_vec, load_server_key, parse_hex, save_client_key, save_rdx_vec,
+    save_server_key,
+};
+use aes128_rdx_bc_fhe::aes128_bitsliced::{
+    decrypt_states_bitsliced_with_fhe, encrypt_states_bitsliced_with_fhe,
+};
+use aes128_rdx_bc_fhe::aes128_keyschedule::{
+    BLOCKSIZE, KeySize, expand_key, key_expansion_with_fhe,
+};
+use aes128_rdx_bc_fhe::aes128_rdx_fhe::{
+    InvMixColumns, decrypt_states_with_fhe, encrypt_states_with_fhe,
+};
+use aes128_rdx_bc_fhe::aes128_sbox::SboxKind;
+use aes128_rdx_bc_fhe::aes128_state::{EncryptedKeySchedule, EncryptedState};
+use aes128_rdx_bc_fhe::error::AesFheError;
+use aes128_rdx_bc_fhe::exec::ExecContext;
+use aes128_rdx_bc_fhe::metrics::Metrics;
+
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Parser)]
+#[command(about = "AES-128/192/256 evaluated under TFHE, split into client and server steps")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Worker threads for the FHE steps, 0 for one per CPU
+    #[arg(long, global = true, default_value_t = 0)]
+    threads: usize,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a client key and the matching server key (client)
+    Keygen {
+        #[arg(long, default_value = "client.key")]
+        client_key: PathBuf,
+        #[arg(long, default_value = "server.key")]
+        server_key: PathBuf,
+        /// Parameter set: 1_1, 2_2 or 4_4 (message_carry bits)
+        #[arg(long, default_value_t = ParamSet::Message2Carry2)]
+        params: ParamSet,
+    },
+    /// Encrypt an AES key given as hex (client)
+    EncryptKey {
+        #[arg(long, default_value = "client.key")]
+        client_key: PathBuf,
+        #[arg(short, long, value_parser = parse_hex_arg)]
+        key: HexBytes,
+        /// Encrypt the raw key and let the server run the key expansion
+        #[arg(long)]
+        raw: bool,
+        #[arg(short, long, default_value = "key.ct")]
+        out: PathBuf,
+    },
+    /// Encrypt one or more 16 byte states given as hex (client)
+    EncryptState {
+        #[arg(long, default_value = "client.key")]
+        client_key: PathBuf,
+        #[arg(short, long, value_parser = parse_hex_arg)]
+        input: HexBytes,
+        #[arg(short, long, default_value = "state.ct")]
+        out: PathBuf,
+    },
+    /// Run the AES cipher on the encrypted states (server)
+    EvalEncrypt {
+        #[command(flatten)]
+        eval: EvalArgs,
+    },
+    /// Run the AES inverse cipher on the encrypted states (server)
+    EvalDecrypt {
+        #[command(flatten)]
+        eval: EvalArgs,
+    },
+    /// Decrypt a result state and print it as hex (client)
+    DecryptResult {
+        #[arg(long, default_value = "client.key")]
+        client_key: PathBuf,
+        #[arg(short, long, default_value = "result.ct")]
+        input: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+struct EvalArgs {
+    #[arg(long, default_value = "server.key")]
+    server_key: PathBuf,
+    #[arg(short, long, default_value = "key.ct")]
+    key: PathBuf,
+    #[arg(short, long, default_value = "state.ct")]
+    input: PathBuf,
+    #[arg(short, long, default_value = "result.ct")]
+    out: PathBuf,
+    /// Number of times the cipher is applied to the state
+    #[arg(short = 'n', long, default_value_t = 1)]
+    iter: usize,
+    /// Keep the state as BooleanBlocks through all rounds
+    #[arg(long)]
+    bitsliced: bool,
+    /// InvMixColumns as a boolean circuit instead of MatchValues lookups
+    #[arg(long)]
+    inv_mix_bc: bool,
+    /// S-box backend: bool-circuit, match-values or nibble-lut
+    #[arg(long, default_value_t = SboxKind::BoolCircuit)]
+    sbox: SboxKind,
+    /// Print per step counts, bootstraps and timings
+    #[arg(long)]
+    metrics: bool,
+}
+
+#[derive(Clone)]
+struct HexBytes(Vec<u8>);
+
+fn parse_hex_arg(s: &str) -> Result<HexBytes, String> {
+    parse_hex(s)
+        .map(HexBytes)
+        .ok_or_else(|| "expected an even number of hex digits".to_string())
+}
+
+fn eval(eval: &EvalArgs, decrypt: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let sk = load_server_key(&eval.server_key)?;
+    let key_ck = load_rdx_vec(&eval.key)?;
+    let state_ck = load_rdx_vec(&eval.input)?;
+
+    AesFheError::check_len(state_ck.len(), BLOCKSIZE)?;
+
+    // several states in one file are evaluated as a batch
+    let mut states = state_ck
+        .chunks_exact(BLOCKSIZE)
+        .map(|state| EncryptedState::new(state.to_vec()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let sbox = eval.sbox.backend();
+
+    let mut metrics = Metrics::new();
+
+    // a raw key is expanded here, round keys are used as they are
+    let xk_ck = if KeySize::from_key_len(key_ck.len()).is_some() {
+        let (xk_ck, m) = key_expansion_with_fhe(&key_ck, sbox.as_ref(), &sk)?;
+        metrics.merge(&m);
+        xk_ck
+    } else {
+        EncryptedKeySchedule::new(key_ck)?
+    };
+
+    let inv_mix = if eval.inv_mix_bc {
+        InvMixColumns::BoolCircuit
+    } else {
+        InvMixColumns::MatchValues
+    };
+
+    let start = Instant::now();
+
+    for _ in 0..eval.iter {
+        let (out, m) = match (decrypt, eval.bitsliced) {
+            (false, false) => encrypt_states_with_fhe(&states, &xk_ck, sbox.as_ref(), &sk)?,
+            (true, false) => decrypt_states_with_fhe(&states, &xk_ck, sbox.as_ref(), inv_mix, &sk)?,
+            (false, true) => encrypt_states_bitsliced_with_fhe(&states, &xk_ck, &sk)?,
+            (true, true) => decrypt_states_bitsliced_with_fhe(&states, &xk_ck, &sk)?,
+        };
+        states = out;
+        metrics.merge(&m);
+    }
+
+    if eval.metrics {
+        println!("{}", metrics);
+    }
+    println!("eval {} iter {:.2?}", eval.iter, start.elapsed());
+
+    let out: Vec<_> = states
+        .into_iter()
+        .flat_map(EncryptedState::into_vec)
+        .collect();
+    save_rdx_vec(&eval.out, &out)?;
+
+    Ok(())
+}
+
+fn run(command: Command) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match command {
+        Command::Keygen {
+            client_key,
+            server_key,
+            params,
+        } => {
+            let (ck, sk) = gen_rdx_keys_with(&FheConfig::new(params));
+            save_client_key(&client_key, &ck)?;
+            save_server_key(&server_key, &sk)?;
+        }
+        Command::EncryptKey {
+            client_key,
+            key: HexBytes(key),
+            raw,
+            out,
+        } => {
+            let key_size =
+                KeySize::from_key_len(key.len()).ok_or(AesFheError::KeyLength(key.len()))?;
+            let ck = load_client_key(&client_key)?;
+
+            if raw {
+                save_rdx_vec(&out, &enc_rdx_vec(&key, &ck))?;
+            } else {
+                enc_round_keys_fhe(&expand_key(&key, key_size)?, &ck)?.save(&out)?;
+            }
+        }
+        Command::EncryptState {
+            client_key,
+            input: HexBytes(input),
+            out,
+        } => {
+            AesFheError::check_len(input.len(), BLOCKSIZE)?;
+            let ck = load_client_key(&client_key)?;
+            save_rdx_vec(&out, &enc_rdx_vec(&input, &ck))?;
+        }
+        Command::EvalEncrypt { eval: args } => eval(&args, false)?,
+        Command::EvalDecrypt { eval: args } => eval(&args, true)?,
+        Command::DecryptResult { client_key, input } => {
+            let ck = load_client_key(&client_key)?;
+            let state_ck = load_rdx_vec(&input)?;
+
+            AesFheError::check_len(state_ck.len(), BLOCKSIZE)?;
+
+            println!("{}", hex(&dec_rdx_vec(&state_ck, &ck)));
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let cli = Cli::parse();
+    let ctx = ExecContext::new(cli.threads)?;
+
+    // keygen, encryption and the rounds all run on the context's pool
+    ctx.install(|| run(cli.command))
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpStats {
+    pub count: usize,
+    pub pbs: u64,
+    pub time: Duration,
+}
+
+impl OpStats {
+    fn add(&mut self, other: &OpStats) {
+        self.count += other.count;
+        self.pbs += other.pbs;
+        self.time += other.time;
+    }
+}
+
+// collected by the evaluator around each step, nothing is printed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub ops: BTreeMap<String, OpStats>,
+    // count is the number of steps in the round
+    pub rounds: Vec<OpStats>,
+    pub total: OpStats,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn time<R>(&mut self, op: &str, f: impl FnOnce() -> R) -> R {
+        let pbs = pbs_count();
+        let start = Instant::now();
+        let res = f();
+        self.record(op, start.elapsed(), pbs_count() - pbs);
+
+        res
+    }
+
+    pub fn record(&mut self, op: &str, time: Duration, pbs: u64) {
+        let stats = OpStats {
+            count: 1,
+            pbs,
+            time,
+        };
+        self.ops.entry(op.to_string()).or_default().add(&stats);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(op, ?time, pbs, "fhe step");
+    }
+
+    // the steps timed inside f make up one round
+    pub fn round<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let steps = self.steps();
+        let pbs = pbs_count();
+        let start = Instant::now();
+        let res = f(self);
+
+        let stats = OpStats {
+            count: self.steps() - steps,
+            pbs: pbs_count() - pbs,
+            time: start.elapsed(),
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(round = self.rounds.len(), time = ?stats.time, pbs = stats.pbs, "fhe round");
+
+        self.rounds.push(stats);
+
+        res
+    }
+
+    // the whole evaluation, so that time spent between steps is accounted too
+    pub fn run<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let steps = self.steps();
+        let pbs = pbs_count();
+        let start = Instant::now();
+        let res = f(self);
+
+        let stats = OpStats {
+            count: self.steps() - steps,
+            pbs: pbs_count() - pbs,
+            time: start.elapsed(),
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::info!(time = ?stats.time, pbs = stats.pbs, steps = stats.count, "fhe run");
+
+        self.total.add(&stats);
+
+        res
+    }
+
+    // runs one after the other, e.g. the iterations of eval-encrypt
+    pub fn merge(&mut self, other: &Metrics) {
+        self.nest(other);
+        self.total.add(&other.total);
+    }
+
+    // a run inside a run of self, the steps and rounds are kept but the total
+    // is already measured by the outer run
+    pub fn nest(&mut self, inner: &Metrics) {
+        for (op, stats) in &inner.ops {
+            self.ops.entry(op.clone()).or_default().add(stats);
+        }
+        self.rounds.extend_from_slice(&inner.rounds);
+    }
+
+    pub fn get(&self, op: &str) -> OpStats {
+        self.ops.get(op).copied().unwrap_or_default()
+    }
+
+    pub fn steps(&self) -> usize {
+        self.ops.values().map(|s| s.count).sum()
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<24}{:>6}{:>10}{:>12}", "op", "count", "pbs", "time")?;
+        for (op, s) in &self.ops {
+            writeln!(f, "{:<24}{:>6}{:>10}{:>12.2?}", op, s.count, s.pbs, s.time)?;
+        }
+        for (i, s) in self.rounds.iter().enumerate() {
+            let round = format!("round {}", i);
+            writeln!(
+                f,
+                "{:<24}{:>6}{:>10}{:>12.2?}",
+                round, s.count, s.pbs, s.time
+            )?;
+        }
+        let t = &self.total;
+        write!(
+            f,
+            "{:<24}{:>6}{:>10}{:>12.2?}",
+            "total", t.count, t.pbs, t.time
+        )
+    }
+}
+
+// bootstraps counted by tfhe, process wide, so concurrent runs are mixed up
+#[cfg(feature = "pbs-stats")]
+#[inline]
+pub fn pbs_count() -> u64 {
+    tfhe::get_pbs_count()
+}
+
+#[cfg(not(feature = "pbs-stats"))]
+#[inline]
+pub fn pbs_count() -> u64 {
+    0
+}
+
+// for the helpers outside the round functions, silent unless tracing is enabled
+#[inline]
+pub fn trace_elapsed(op: &str, start: Instant) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(op, time = ?start.elapsed(), "fhe step");
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = (op, start);
+}
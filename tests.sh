@@ -1,4 +1,6 @@
 cargo test --release -- --nocapture ::test_key_expansion -- --exact
+cargo test --release -- --nocapture ::test_expand_key -- --exact
+cargo test --release -- --nocapture ::test_key_expansion_fhe -- --exact
 cargo test --release -- --nocapture ::test_init_arr_ciphertext ::test_init_vec_ciphertext -- --exact
 cargo test --release -- --nocapture ::test_match_value_lookup_sbox_tfhe -- --exact
 cargo test --release -- --nocapture ::test_perf_rdx_xor -- --exact
@@ -6,11 +8,101 @@ cargo test --release -- --nocapture ::test_encrypt_block_tfhe1 -- --exact
 cargo test --release -- --nocapture ::test_decrypt_block_tfhe1 -- --exact
 cargo test --release -- --nocapture ::test_encrypt_block_tfhe2 -- --exact
 cargo test --release -- --nocapture ::test_decrypt_block_tfhe2 -- --exact
+cargo test --release -- --nocapture ::test_encrypt_decrypt_block_aes192 -- --exact
+cargo test --release -- --nocapture ::test_encrypt_decrypt_block_aes256 -- --exact
 cargo test --release -- --nocapture ::test_encrypt_decrypt_rnd_block -- --exact
+cargo test --release -- --nocapture ::test_client_server_block -- --exact
 cargo test --release -- --nocapture ::test_sbox -- --exact
 cargo test --release -- --nocapture ::test_mix -- --exact
+cargo test --release -- --nocapture ::test_ctr_block -- --exact
+cargo test --release -- --nocapture ::test_ctr_transcipher -- --exact
+cargo test --release -- --nocapture ::test_ofb_keystream -- --exact
+cargo test --release -- --nocapture ::test_ofb_encrypt_decrypt -- --exact
+cargo test --release -- --nocapture ::test_pkcs7 -- --exact
+cargo test --release -- --nocapture ::test_cbc_encrypt -- --exact
+cargo test --release -- --nocapture ::test_cbc_decrypt -- --exact
+cargo test --release -- --nocapture ::test_cmac_subkeys -- --exact
+cargo test --release -- --nocapture ::test_cmac_rfc4493 -- --exact
+cargo test --release -- --nocapture ::test_gcm_counter_block -- --exact
+cargo test --release -- --nocapture ::test_gcm_encrypt -- --exact
+cargo test --release -- --nocapture ::test_gcm_transcipher -- --exact
+cargo test --release -- --nocapture ::test_gcm_transcipher_bad_tag -- --exact
+cargo test --release -- --nocapture ::test_xts_encrypt -- --exact
+cargo test --release -- --nocapture ::test_xts_decrypt -- --exact
+cargo test --release -- --nocapture ::test_save_load_keys_and_state -- --exact
+cargo test --release -- --nocapture ::test_load_truncated_state -- --exact
+cargo test --release -- --nocapture ::test_sbox_circuit_plain -- --exact
+cargo test --release -- --nocapture ::test_mix_cols_circuit_plain -- --exact
+cargo test --release -- --nocapture ::test_circuit_from_instr_unordered -- --exact
+cargo test --release -- --nocapture ::test_circuit_eval_fhe -- --exact
+cargo test --release -- --nocapture ::test_bristol_parse -- --exact
+cargo test --release -- --nocapture ::test_bristol_roundtrip_sbox -- --exact
+cargo test --release -- --nocapture ::test_circuit_load -- --exact
+cargo test --release -- --nocapture ::test_circuit_parse_errors -- --exact
+cargo test --release -- --nocapture ::test_circuit_width_errors -- --exact
+cargo test --release -- --nocapture ::test_shift_rows_perm -- --exact
+cargo test --release -- --nocapture ::test_encrypt_state_bitsliced -- --exact
+cargo test --release -- --nocapture ::test_decrypt_state_bitsliced -- --exact
+cargo test --release -- --nocapture ::test_states_bitsliced_metrics -- --exact
+cargo test --release -- --nocapture ::test_inv_mix_cols_circuit_plain -- --exact
+cargo test --release -- --nocapture ::test_inv_mix_cols_1 -- --exact
+cargo test --release -- --nocapture ::test_decrypt_state_inv_mix_bc -- --exact
+cargo test --release -- --nocapture ::test_inv_sbox_table -- --exact
+cargo test --release -- --nocapture ::test_sbox_kind_from_str -- --exact
+cargo test --release -- --nocapture ::test_sbox_circuit_backend -- --exact
+cargo test --release -- --nocapture ::test_sbox_match_values_backend -- --exact
+cargo test --release -- --nocapture ::test_sbox_nibble_lut_backend -- --exact
+cargo test --release -- --nocapture ::test_encrypt_decrypt_match_values -- --exact
 
-cargo run --release -- -n 1 -i "0123456789abcdef" -k "0123456789abcdef0123456789abcdef"
-cargo run --release -- -n 2 -i "0123456789abcdef" -k "0123456789abcdef0123456789abcdef"
+cargo test --release -- --nocapture ::test_param_set_from_str -- --exact
+cargo test --release -- --nocapture ::test_config_message_1_carry_1 -- --exact
+cargo test --release -- --nocapture ::test_config_message_2_carry_2 -- --exact
+cargo test --release -- --nocapture ::test_config_message_4_carry_4 -- --exact
+cargo test --release -- --nocapture ::test_fips197_message_1_carry_1 -- --exact
+cargo test --release -- --nocapture ::test_fips197_message_2_carry_2 -- --exact
+cargo test --release -- --nocapture ::test_fips197_message_4_carry_4 -- --exact
+
+cargo test --release -- --nocapture ::test_metrics_rounds -- --exact
+cargo test --release -- --nocapture ::test_metrics_merge -- --exact
+cargo test --release -- --nocapture ::test_metrics_nest -- --exact
+
+cargo test --release -- --nocapture ::test_ref_fips197 -- --exact
+cargo test --release -- --nocapture ::test_ref_vs_aes_crate -- --exact
+cargo test --release -- --nocapture ::test_ref_steps -- --exact
+cargo test --release -- --nocapture ::test_fhe_steps_vs_ref -- --exact
+
+cargo test --release -- --nocapture ::test_batch_steps -- --exact
+cargo test --release -- --nocapture ::test_encrypt_decrypt_states -- --exact
+
+cargo test --release -- --nocapture ::test_exec_threads -- --exact
+cargo test --release -- --nocapture ::test_exec_fhe_step -- --exact
+
+cargo test --release -- --nocapture ::test_error_state_and_key_length -- --exact
+cargo test --release -- --nocapture ::test_error_param_mismatch -- --exact
+cargo test --release -- --nocapture ::test_error_circuit_and_deserialize -- --exact
+
+cargo test --release -- --nocapture ::test_state_layout -- --exact
+cargo test --release -- --nocapture ::test_key_schedule_round_keys -- --exact
+cargo test --release -- --nocapture ::test_state_and_key_schedule_save_load -- --exact
+
+cargo test --release -- --nocapture ::test_hex -- --exact
+cargo test --release -- --nocapture ::test_kat_parse -- --exact
+cargo test --release -- --nocapture ::test_kat_sample -- --exact
+cargo test --release -- --nocapture ::test_kat_reference -- --exact
+cargo test --release -- --nocapture ::test_kat_fhe -- --exact
+
+cargo test --release -- --nocapture ::test_cli_malformed_hex -- --exact
+
+cargo test --release --features debug-trace -- --nocapture ::test_reference_trace -- --exact
+cargo test --release --features debug-trace -- --nocapture ::test_first_divergence -- --exact
+cargo test --release --features debug-trace -- --nocapture ::test_trace_encrypt_decrypt_fhe -- --exact
+
+cargo run --release -- keygen
+cargo run --release -- encrypt-key -k "000102030405060708090a0b0c0d0e0f"
+cargo run --release -- encrypt-state -i "00112233445566778899aabbccddeeff"
+cargo run --release -- eval-encrypt -n 1
+cargo run --release -- decrypt-result
+cargo run --release -- eval-decrypt -i result.ct -o state.ct -n 1
+cargo run --release -- decrypt-result -i state.ct
 
 echo "READY!"
@@ -0,0 +1,82 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    dec_rdx_vec, dec_state_fhe, enc_rdx_vec, enc_round_keys_fhe, enc_state_fhe, gen_rdx_keys,
+};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::PosVals;
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{
+    add_round_key_fhe, decrypt_states_fhe, encrypt_states_with_fhe, inv_mix_columns_fhe,
+    mix_columns_fhe, shift_rows_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_ref::{
+    add_round_key, decrypt_state, encrypt_state, inv_mix_columns, mix_columns, shift_rows,
+};
+pub use aes128_rdx_bc_fhe::aes128_sbox::SboxCircuit;
+pub use aes128_rdx_bc_fhe::aes128_tables::{GMUL9, GMULB, GMULD, GMULE, gen_tbl};
+
+pub const BATCH: usize = 3;
+
+pub fn random_states(n: usize) -> Vec<[u8; 16]> {
+    (0..n).map(|_| rand::random()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_steps() {
+        let (ck, sk) = gen_rdx_keys();
+        let pos_vals = PosVals::new_trivial(&sk);
+
+        let mut states = random_states(BATCH);
+        let rkey: [u8; 16] = rand::random();
+        let mut batch_ck = enc_rdx_vec(&states.concat(), &ck);
+
+        // one round key for every state of the batch
+        add_round_key_fhe(&mut batch_ck, &enc_state_fhe(&rkey, &ck), &sk).unwrap();
+        shift_rows_fhe(&mut batch_ck).unwrap();
+        mix_columns_fhe(&mut batch_ck, &pos_vals, &sk).unwrap();
+
+        let [g9, gb, gd, ge] = [&GMUL9, &GMULB, &GMULD, &GMULE].map(gen_tbl);
+        inv_mix_columns_fhe(&mut batch_ck, &g9, &gb, &gd, &ge, &sk).unwrap();
+
+        for state in states.iter_mut() {
+            add_round_key(state, &rkey);
+            shift_rows(state);
+            mix_columns(state);
+            inv_mix_columns(state);
+        }
+
+        assert_eq!(dec_rdx_vec(&batch_ck, &ck), states.concat());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_states() {
+        let (ck, sk) = gen_rdx_keys();
+        let key: [u8; 16] = rand::random();
+        let xk = key_expansion(&key);
+        let xk_ck = enc_round_keys_fhe(&xk, &ck).unwrap();
+
+        let states = random_states(BATCH);
+        let states_ck: Vec<_> = states.iter().map(|s| enc_state_fhe(s, &ck)).collect();
+
+        let (enc_ck, metrics) =
+            encrypt_states_with_fhe(&states_ck, &xk_ck, &SboxCircuit, &sk).unwrap();
+        println!("{}", metrics);
+        assert_eq!(enc_ck.len(), BATCH);
+        assert_eq!(metrics.rounds.len(), 11);
+
+        for (state, enc) in states.iter().zip(&enc_ck) {
+            assert_eq!(dec_state_fhe(enc, &ck).unwrap(), encrypt_state(state, &xk));
+        }
+
+        let dec_ck = decrypt_states_fhe(&enc_ck, &xk_ck, &sk).unwrap();
+        for (state, dec) in states.iter().zip(&dec_ck) {
+            assert_eq!(dec_state_fhe(dec, &ck).unwrap(), *state);
+        }
+        assert_eq!(
+            decrypt_state(&dec_state_fhe(&enc_ck[0], &ck).unwrap(), &xk),
+            states[0]
+        );
+    }
+}
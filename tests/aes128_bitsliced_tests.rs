@@ -0,0 +1,91 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    dec_state_fhe, enc_round_keys_fhe, enc_state_fhe, gen_rdx_keys,
+};
+pub use aes128_rdx_bc_fhe::aes128_bitsliced::{
+    decrypt_state_bitsliced_fhe, encrypt_state_bitsliced_fhe, encrypt_states_bitsliced_with_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+pub use aes128_rdx_bc_fhe::aes128_ref::encrypt_state;
+pub use aes128_rdx_bc_fhe::aes128_tables::{INV_SHIFT_ROWS, SHIFT_ROWS, permute_blocks};
+
+// FIPS-197 C.1
+pub const PLAINTEXT: [u8; 16] = [
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+];
+pub const KEY: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+pub const CIPHERTEXT: [u8; 16] = [
+    0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shift_rows_perm() {
+        // FIPS-197 C.1, round 1 s_box -> s_row
+        let mut state: [u8; 16] = [
+            0x63, 0xca, 0xb7, 0x04, 0x09, 0x53, 0xd0, 0x51, 0xcd, 0x60, 0xe0, 0xe7, 0xba, 0x70,
+            0xe1, 0x8c,
+        ];
+        let s_row: [u8; 16] = [
+            0x63, 0x53, 0xe0, 0x8c, 0x09, 0x60, 0xe1, 0x04, 0xcd, 0x70, 0xb7, 0x51, 0xba, 0xca,
+            0xd0, 0xe7,
+        ];
+
+        permute_blocks(&mut state, &SHIFT_ROWS);
+        assert_eq!(state, s_row);
+
+        permute_blocks(&mut state, &INV_SHIFT_ROWS);
+        permute_blocks(&mut state, &SHIFT_ROWS);
+        assert_eq!(state, s_row);
+    }
+
+    #[test]
+    fn test_encrypt_state_bitsliced() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&KEY), &ck).unwrap();
+        let state_ck = enc_state_fhe(&PLAINTEXT, &ck);
+
+        let enc_ck = encrypt_state_bitsliced_fhe(&state_ck, &xk_ck, &sk).unwrap();
+
+        assert_eq!(dec_state_fhe(&enc_ck, &ck).unwrap(), CIPHERTEXT);
+    }
+
+    #[test]
+    fn test_decrypt_state_bitsliced() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&KEY), &ck).unwrap();
+        let state_ck = enc_state_fhe(&CIPHERTEXT, &ck);
+
+        let dec_ck = decrypt_state_bitsliced_fhe(&state_ck, &xk_ck, &sk).unwrap();
+
+        assert_eq!(dec_state_fhe(&dec_ck, &ck).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn test_states_bitsliced_metrics() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk = key_expansion(&KEY);
+        let xk_ck = enc_round_keys_fhe(&xk, &ck).unwrap();
+        let states = [PLAINTEXT, [0u8; 16]];
+        let states_ck: Vec<_> = states.iter().map(|s| enc_state_fhe(s, &ck)).collect();
+
+        let (enc_ck, metrics) = encrypt_states_bitsliced_with_fhe(&states_ck, &xk_ck, &sk).unwrap();
+        println!("{}", metrics);
+
+        for (state, enc) in states.iter().zip(&enc_ck) {
+            assert_eq!(dec_state_fhe(enc, &ck).unwrap(), encrypt_state(state, &xk));
+        }
+
+        // the same rounds and steps as the radix path, plus the conversions
+        assert_eq!(metrics.rounds.len(), 11);
+        assert_eq!(metrics.get("add_round_key").count, 11);
+        assert_eq!(metrics.get("mix_columns").count, 9);
+        assert_eq!(metrics.get("to_bits").count, 2);
+        assert_eq!(metrics.get("from_bits").count, 1);
+        assert_eq!(metrics.total.count, metrics.steps());
+    }
+}
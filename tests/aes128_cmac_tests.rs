@@ -0,0 +1,90 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{dec_rdx_vec, enc_rdx_vec, enc_round_keys_fhe, gen_rdx_keys};
+pub use aes128_rdx_bc_fhe::aes128_cmac::{cmac_fhe, cmac_subkeys_fhe};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+
+pub struct CmacTest {
+    pub len: usize,
+    pub tag: [u8; 16],
+}
+
+// RFC 4493 4.
+pub const CMAC_KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+
+pub const CMAC_MSG: [u8; 64] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+    0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+    0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+    0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+];
+
+pub const CMAC_TESTS: &[CmacTest] = &[
+    // Example 1, empty message
+    CmacTest {
+        len: 0,
+        tag: [
+            0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28, 0x7f, 0xa3, 0x7d, 0x12, 0x9b, 0x75,
+            0x67, 0x46,
+        ],
+    },
+    // Example 2
+    CmacTest {
+        len: 16,
+        tag: [
+            0x07, 0x0a, 0x16, 0xb4, 0x6b, 0x4d, 0x41, 0x44, 0xf7, 0x9b, 0xdd, 0x9d, 0xd0, 0x4a,
+            0x28, 0x7c,
+        ],
+    },
+    // Example 3
+    CmacTest {
+        len: 40,
+        tag: [
+            0xdf, 0xa6, 0x67, 0x47, 0xde, 0x9a, 0xe6, 0x30, 0x30, 0xca, 0x32, 0x61, 0x14, 0x97,
+            0xc8, 0x27,
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmac_subkeys() {
+        let k1: [u8; 16] = [
+            0xfb, 0xee, 0xd6, 0x18, 0x35, 0x71, 0x33, 0x66, 0x7c, 0x85, 0xe0, 0x8f, 0x72, 0x36,
+            0xa8, 0xde,
+        ];
+        let k2: [u8; 16] = [
+            0xf7, 0xdd, 0xac, 0x30, 0x6a, 0xe2, 0x66, 0xcc, 0xf9, 0x0b, 0xc1, 0x1e, 0xe4, 0x6d,
+            0x51, 0x3b,
+        ];
+
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&CMAC_KEY), &ck).unwrap();
+
+        let (k1_ck, k2_ck) = cmac_subkeys_fhe(&xk_ck, &sk).unwrap();
+
+        assert_eq!(dec_rdx_vec(&k1_ck, &ck), k1);
+        assert_eq!(dec_rdx_vec(&k2_ck, &ck), k2);
+    }
+
+    #[test]
+    fn test_cmac_rfc4493() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&CMAC_KEY), &ck).unwrap();
+
+        for (i, test) in CMAC_TESTS.iter().enumerate() {
+            let msg_ck = enc_rdx_vec(&CMAC_MSG[..test.len], &ck);
+            let tag_ck = cmac_fhe(&xk_ck, &msg_ck, &sk).unwrap();
+
+            assert_eq!(
+                dec_rdx_vec(&tag_ck, &ck),
+                test.tag,
+                "cmac example {} failed",
+                i + 1
+            );
+        }
+    }
+}
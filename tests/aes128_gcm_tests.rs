@@ -0,0 +1,95 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{dec_rdx_vec, enc_rdx_vec, enc_round_keys_fhe, gen_rdx_keys};
+pub use aes128_rdx_bc_fhe::aes128_gcm::{gcm_counter_block, gcm_encrypt_fhe, gcm_transcipher_fhe};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+
+// GCM spec. test case 4 key and iv, message and aad cut to cover partial blocks
+pub const GCM_KEY: [u8; 16] = [
+    0xfe, 0xff, 0xe9, 0x92, 0x86, 0x65, 0x73, 0x1c, 0x6d, 0x6a, 0x8f, 0x94, 0x67, 0x30, 0x83, 0x08,
+];
+
+pub const GCM_IV: [u8; 12] = [
+    0xca, 0xfe, 0xba, 0xbe, 0xfa, 0xce, 0xdb, 0xad, 0xde, 0xca, 0xf8, 0x88,
+];
+
+pub const GCM_AAD: [u8; 13] = [
+    0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef, 0xfe, 0xed, 0xfa, 0xce, 0xde,
+];
+
+pub const GCM_PLAINTEXT: [u8; 20] = [
+    0xd9, 0x31, 0x32, 0x25, 0xf8, 0x84, 0x06, 0xe5, 0xa5, 0x59, 0x09, 0xc5, 0xaf, 0xf5, 0x26, 0x9a,
+    0x86, 0xa7, 0xa9, 0x53,
+];
+
+pub const GCM_CIPHERTEXT: [u8; 20] = [
+    0x42, 0x83, 0x1e, 0xc2, 0x21, 0x77, 0x74, 0x24, 0x4b, 0x72, 0x21, 0xb7, 0x84, 0xd0, 0xd4, 0x9c,
+    0xe3, 0xaa, 0x21, 0x2f,
+];
+
+pub const GCM_TAG: [u8; 16] = [
+    0xf0, 0x0e, 0xfb, 0x12, 0xba, 0xb3, 0x22, 0xeb, 0xbb, 0x1f, 0x53, 0xd5, 0x82, 0x2a, 0xaf, 0xaf,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcm_counter_block() {
+        let mut j0 = [0xab; 16];
+        j0[12..].copy_from_slice(&[0xff, 0xff, 0xff, 0xfe]);
+
+        let mut expected = [0xab; 16];
+        expected[12..].copy_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+
+        // inc_32 wraps without carry into the iv
+        assert_eq!(gcm_counter_block(&j0, 3), expected);
+    }
+
+    // GCM spec. test case 2
+    #[test]
+    fn test_gcm_encrypt() {
+        let expected_ciphertext: [u8; 16] = [
+            0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2,
+            0xfe, 0x78,
+        ];
+        let expected_tag: [u8; 16] = [
+            0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57,
+            0xbd, 0xdf,
+        ];
+
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&[0u8; 16]), &ck).unwrap();
+        let pt_ck = enc_rdx_vec(&[0u8; 16], &ck);
+
+        let (ct_ck, tag_ck) = gcm_encrypt_fhe(&xk_ck, &[0u8; 12], &[], &pt_ck, &sk).unwrap();
+
+        assert_eq!(dec_rdx_vec(&ct_ck, &ck), expected_ciphertext);
+        assert_eq!(dec_rdx_vec(&tag_ck, &ck), expected_tag);
+    }
+
+    #[test]
+    fn test_gcm_transcipher() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&GCM_KEY), &ck).unwrap();
+
+        let (pt_ck, valid) =
+            gcm_transcipher_fhe(&xk_ck, &GCM_IV, &GCM_AAD, &GCM_CIPHERTEXT, &GCM_TAG, &sk).unwrap();
+
+        assert_eq!(dec_rdx_vec(&pt_ck, &ck), GCM_PLAINTEXT);
+        assert!(ck.decrypt_bool(&valid));
+    }
+
+    #[test]
+    fn test_gcm_transcipher_bad_tag() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&GCM_KEY), &ck).unwrap();
+
+        let mut tag = GCM_TAG;
+        tag[15] ^= 0x01;
+
+        let (_, valid) =
+            gcm_transcipher_fhe(&xk_ck, &GCM_IV, &GCM_AAD, &GCM_CIPHERTEXT, &tag, &sk).unwrap();
+
+        assert!(!ck.decrypt_bool(&valid));
+    }
+}
@@ -0,0 +1,247 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{gen_rdx_keys, hex, parse_hex};
+pub use aes128_rdx_bc_fhe::aes128_kat::{
+    FheCipher, KatDirection, KatKind, KatOptions, RefCipher, load_rsp, load_rsp_dir, parse_rsp,
+    run_kat, sample_vectors,
+};
+pub use aes128_rdx_bc_fhe::error::AesFheError;
+
+mod common;
+pub use common::test_dir;
+
+use aes::Aes128;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArray};
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+// AES_KAT_DIR holds the .rsp files, by default the excerpts in tests/kat
+pub fn kat_dir() -> PathBuf {
+    env::var_os("AES_KAT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/kat"))
+}
+
+// AES_KAT_SAMPLE vectors per file and direction ("all" or a number, 1 by
+// default), AES_KAT_SEED picks them, AES_KAT_MCT=1 adds the Monte Carlo files
+pub fn kat_options() -> KatOptions {
+    let var = |name: &str| env::var(name).ok();
+
+    KatOptions {
+        sample: match var("AES_KAT_SAMPLE").as_deref() {
+            Some("all") => None,
+            Some(n) => Some(n.parse().expect("AES_KAT_SAMPLE is a number or all")),
+            None => Some(1),
+        },
+        seed: var("AES_KAT_SEED").map_or(0, |s| s.parse().expect("AES_KAT_SEED is a number")),
+        mct: var("AES_KAT_MCT").is_some_and(|s| s == "1"),
+    }
+}
+
+// AESAVS 6.4 for 128 bit keys, computed with the aes crate
+pub fn mct_rsp(key: [u8; 16], block: [u8; 16], records: usize) -> String {
+    let mut text = String::from("# AESVS MCT test data for ECB\n");
+
+    for (section, encrypt) in [("ENCRYPT", true), ("DECRYPT", false)] {
+        writeln!(text, "\n[{}]", section).unwrap();
+        let (mut key, mut input) = (key, block);
+
+        for count in 0..records {
+            let cipher = Aes128::new(&GenericArray::from(key));
+            let mut out = GenericArray::from(input);
+            for _ in 0..1000 {
+                match encrypt {
+                    true => cipher.encrypt_block(&mut out),
+                    false => cipher.decrypt_block(&mut out),
+                }
+            }
+
+            let (pt, ct) = match encrypt {
+                true => (input, out.into()),
+                false => (out.into(), input),
+            };
+            let (first, second) = match encrypt {
+                true => (("PLAINTEXT", pt), ("CIPHERTEXT", ct)),
+                false => (("CIPHERTEXT", ct), ("PLAINTEXT", pt)),
+            };
+            writeln!(text, "\nCOUNT = {}\nKEY = {}", count, hex(&key)).unwrap();
+            writeln!(text, "{} = {}", first.0, hex(&first.1)).unwrap();
+            writeln!(text, "{} = {}", second.0, hex(&second.1)).unwrap();
+
+            key.iter_mut().zip(out).for_each(|(k, o)| *k ^= o);
+            input = out.into();
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        let bytes = parse_hex("00ff3A").unwrap();
+        assert_eq!(bytes, vec![0x00, 0xff, 0x3a]);
+        assert_eq!(hex(&bytes), "00ff3a");
+        assert_eq!(parse_hex(""), Some(vec![]));
+
+        // multi byte chars would split inside a char if sliced by byte offset
+        for s in ["abc", "0g", "aé0", "é0", "00 11", "+1"] {
+            assert_eq!(parse_hex(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_kat_parse() {
+        assert_eq!(
+            KatKind::from_file_name("ECBGFSbox128.rsp"),
+            Some(KatKind::GfSbox)
+        );
+        assert_eq!(KatKind::from_file_name("ECBMCT256.rsp"), Some(KatKind::Mct));
+        assert_eq!(KatKind::from_file_name("CBCVarTxt128.rsp"), None);
+
+        let vectors = load_rsp(&kat_dir().join("ECBGFSbox128.rsp")).unwrap();
+        assert_eq!(vectors.len(), 4);
+        let v = &vectors[2];
+        assert_eq!((v.direction, v.count), (KatDirection::Decrypt, 0));
+        assert_eq!(v.key, vec![0u8; 16]);
+        assert_eq!(hex(v.input()), "0336763e966d92595a567cc9ce537f5e");
+        assert_eq!(hex(v.expected()), "f34481ec3cc627bacd5dc3fb08f273e6");
+        assert_eq!(v.to_string(), "ECBGFSbox128.rsp DECRYPT COUNT = 0");
+
+        let malformed = [
+            "[ENCRYPT]\nCOUNT = 0\nKEY = 00\nPLAINTEXT = 00",
+            "[ENCRYPT]\nCOUNT = 0\nKEY = 0g\n",
+            "[ENCRYPT]\nCOUNT = x\n",
+            "[MONTE]\n",
+            "COUNT = 0\n",
+            "[ENCRYPT]\nKEY\n",
+        ];
+        for text in malformed {
+            let err = parse_rsp("ECBVarKey128.rsp", KatKind::VarKey, text).unwrap_err();
+            println!("{}", err);
+            assert!(matches!(err, AesFheError::MalformedVector(_)), "{}", text);
+        }
+        assert!(matches!(
+            parse_rsp(
+                "ECBVarKey128.rsp",
+                KatKind::VarKey,
+                "[ENCRYPT]\nCOUNT = 0\nKEY = 00\nPLAINTEXT = 00000000000000000000000000000000\n\
+                 CIPHERTEXT = 00000000000000000000000000000000\n",
+            )
+            .unwrap_err(),
+            AesFheError::KeyLength(1)
+        ));
+
+        // other modes and files are skipped
+        let dir = test_dir("kat_parse");
+        fs::copy(
+            kat_dir().join("ECBVarTxt128.rsp"),
+            dir.join("ECBVarTxt128.rsp"),
+        )
+        .unwrap();
+        fs::write(dir.join("CBCVarTxt128.rsp"), "[ENCRYPT]\nIV = 00\n").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        assert_eq!(load_rsp_dir(&dir).unwrap().len(), 4);
+        assert!(matches!(
+            load_rsp(&dir.join("CBCVarTxt128.rsp")).unwrap_err(),
+            AesFheError::MalformedVector(_)
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_kat_sample() {
+        let dir = test_dir("kat_sample");
+        fs::write(dir.join("ECBMCT128.rsp"), mct_rsp([0u8; 16], [0u8; 16], 3)).unwrap();
+        fs::copy(
+            kat_dir().join("ECBGFSbox128.rsp"),
+            dir.join("ECBGFSbox128.rsp"),
+        )
+        .unwrap();
+        let vectors = load_rsp_dir(&dir).unwrap();
+        assert_eq!(vectors.len(), 4 + 6);
+
+        // the Monte Carlo records only when asked for
+        assert_eq!(sample_vectors(&vectors, &KatOptions::default()).len(), 4);
+        let all = KatOptions {
+            mct: true,
+            ..KatOptions::default()
+        };
+        assert_eq!(sample_vectors(&vectors, &all).len(), 10);
+
+        let options = KatOptions {
+            sample: Some(1),
+            seed: 7,
+            mct: true,
+        };
+        let sampled = sample_vectors(&vectors, &options);
+        assert_eq!(sampled.len(), 4);
+        assert_eq!(sampled, sample_vectors(&vectors, &options));
+        let groups: Vec<_> = sampled.iter().map(|v| (&v.file, v.direction)).collect();
+        groups.windows(2).for_each(|w| assert_ne!(w[0], w[1]));
+
+        let no_mct = KatOptions {
+            mct: false,
+            ..options
+        };
+        assert!(
+            sample_vectors(&vectors, &no_mct)
+                .iter()
+                .all(|v| v.kind != KatKind::Mct)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_kat_reference() {
+        let vectors = load_rsp_dir(&kat_dir()).unwrap();
+        let report = run_kat(
+            &sample_vectors(&vectors, &KatOptions::default()),
+            &RefCipher,
+            |_| (),
+        );
+        println!("{}", report);
+        assert!(report.all_passed());
+
+        let dir = test_dir("kat_reference");
+        let path = dir.join("ECBMCT128.rsp");
+        fs::write(&path, mct_rsp(rand::random(), rand::random(), 2)).unwrap();
+        let vectors = load_rsp(&path).unwrap();
+        let report = run_kat(&vectors.iter().collect::<Vec<_>>(), &RefCipher, |_| ());
+        assert_eq!(report.passed(), 4);
+
+        // a wrong answer is reported, not panicked on
+        let mut wrong = vectors[0].clone();
+        wrong.ciphertext[0] ^= 1;
+        let report = run_kat(&[&wrong], &RefCipher, |_| ());
+        assert_eq!(report.failed().count(), 1);
+        assert!(report.to_string().contains("FAIL"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_kat_fhe() {
+        let (ck, sk) = gen_rdx_keys();
+        let options = kat_options();
+
+        let vectors = load_rsp_dir(&kat_dir()).unwrap();
+        let sampled = sample_vectors(&vectors, &options);
+        println!(
+            "{} of {} vectors from {}",
+            sampled.len(),
+            vectors.len(),
+            kat_dir().display()
+        );
+
+        // one FHE block takes minutes, show each result as it comes
+        let report = run_kat(&sampled, &FheCipher::new(&ck, &sk), |r| println!("{}", r));
+        println!("{}", report);
+        assert!(report.all_passed());
+    }
+}
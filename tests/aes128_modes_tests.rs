@@ -0,0 +1,167 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    dec_rdx_vec, enc_rdx_vec, enc_round_keys_fhe, enc_state_fhe, gen_rdx_keys, pkcs7_pad,
+    pkcs7_unpad,
+};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+pub use aes128_rdx_bc_fhe::aes128_modes::{
+    cbc_decrypt_fhe, cbc_encrypt_fhe, ctr_block, ctr_transcipher_with_fhe, ofb_keystream_fhe,
+    ofb_xor_fhe, ofb_xor_plain_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_sbox::SboxCircuit;
+
+// SP 800-38A F.5.1, F.4.1, F.2.1 share the key and plaintext
+pub const SP800_38A_KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+
+pub const SP800_38A_PLAINTEXT: [u8; 48] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+    0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+    0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+];
+
+pub const SP800_38A_IV: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+
+// SP 800-38A F.4.1 OFB-AES128.Encrypt
+pub const OFB_CIPHERTEXT: [u8; 48] = [
+    0x3b, 0x3f, 0xd9, 0x2e, 0xb7, 0x2d, 0xad, 0x20, 0x33, 0x34, 0x49, 0xf8, 0xe8, 0x3c, 0xfb, 0x4a,
+    0x77, 0x89, 0x50, 0x8d, 0x16, 0x91, 0x8f, 0x03, 0xf5, 0x3c, 0x52, 0xda, 0xc5, 0x4e, 0xd8, 0x25,
+    0x97, 0x40, 0x05, 0x1e, 0x9c, 0x5f, 0xec, 0xf6, 0x43, 0x44, 0xf7, 0xa8, 0x22, 0x60, 0xed, 0xcc,
+];
+
+// SP 800-38A F.2.1 CBC-AES128.Encrypt
+pub const CBC_CIPHERTEXT: [u8; 48] = [
+    0x76, 0x49, 0xab, 0xac, 0x81, 0x19, 0xb2, 0x46, 0xce, 0xe9, 0x8e, 0x9b, 0x12, 0xe9, 0x19, 0x7d,
+    0x50, 0x86, 0xcb, 0x9b, 0x50, 0x72, 0x19, 0xee, 0x95, 0xdb, 0x11, 0x3a, 0x91, 0x76, 0x78, 0xb2,
+    0x73, 0xbe, 0xd6, 0xb8, 0xe3, 0xc1, 0x74, 0x3b, 0x71, 0x16, 0xe6, 0x9e, 0x22, 0x22, 0x95, 0x16,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ctr_block() {
+        let nonce = [0xff; 16];
+        assert_eq!(ctr_block(&nonce, 1), [0x00; 16]);
+
+        let mut nonce = [0x00; 16];
+        nonce[15] = 0xfe;
+        let mut expected = [0x00; 16];
+        expected[14] = 0x01;
+        expected[15] = 0x01;
+        assert_eq!(ctr_block(&nonce, 3), expected);
+    }
+
+    #[test]
+    fn test_ctr_transcipher() {
+        let nonce: [u8; 16] = [
+            0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd,
+            0xfe, 0xff,
+        ];
+        // two blocks and a partial third block
+        let ciphertext: [u8; 36] = [
+            0x87, 0x4d, 0x61, 0x91, 0xb6, 0x20, 0xe3, 0x26, 0x1b, 0xef, 0x68, 0x64, 0x99, 0x0d,
+            0xb6, 0xce, 0x98, 0x06, 0xf6, 0x6b, 0x79, 0x70, 0xfd, 0xff, 0x86, 0x17, 0x18, 0x7b,
+            0xb9, 0xff, 0xfd, 0xff, 0x5a, 0xe4, 0xdf, 0x3e,
+        ];
+
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&SP800_38A_KEY), &ck).unwrap();
+
+        let (plain_ck, metrics) =
+            ctr_transcipher_with_fhe(&xk_ck, &nonce, &ciphertext, &SboxCircuit, &sk).unwrap();
+        let plain = dec_rdx_vec(&plain_ck, &ck);
+
+        assert_eq!(plain, SP800_38A_PLAINTEXT[..ciphertext.len()]);
+
+        // the three counter blocks are one batch through the rounds
+        assert_eq!(metrics.rounds.len(), 11);
+        assert_eq!(metrics.get("keystream_xor").count, 1);
+        assert_eq!(metrics.total.count, metrics.steps());
+        println!("{}", metrics);
+    }
+
+    #[test]
+    fn test_ofb_keystream() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&SP800_38A_KEY), &ck).unwrap();
+        let iv_ck = enc_state_fhe(&SP800_38A_IV, &ck);
+
+        let keystream = ofb_keystream_fhe(&xk_ck, &iv_ck, 2, &sk).unwrap();
+        assert_eq!(keystream.len(), 2);
+
+        for (i, block) in keystream.iter().enumerate() {
+            let ks = dec_rdx_vec(block, &ck);
+            for (j, &k) in ks.iter().enumerate() {
+                let n = i * 16 + j;
+                assert_eq!(k ^ SP800_38A_PLAINTEXT[n], OFB_CIPHERTEXT[n]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ofb_encrypt_decrypt() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&SP800_38A_KEY), &ck).unwrap();
+        let iv_ck = enc_state_fhe(&SP800_38A_IV, &ck);
+
+        // one block and a partial second block
+        let len = 20;
+        let ct_ck = ofb_xor_plain_fhe(&xk_ck, &iv_ck, &SP800_38A_PLAINTEXT[..len], &sk).unwrap();
+        assert_eq!(dec_rdx_vec(&ct_ck, &ck), OFB_CIPHERTEXT[..len]);
+
+        let pt_ck = ofb_xor_fhe(&xk_ck, &iv_ck, &ct_ck, &sk).unwrap();
+        assert_eq!(dec_rdx_vec(&pt_ck, &ck), SP800_38A_PLAINTEXT[..len]);
+
+        let ct_ck = ofb_xor_fhe(
+            &xk_ck,
+            &iv_ck,
+            &enc_rdx_vec(&SP800_38A_PLAINTEXT[..len], &ck),
+            &sk,
+        )
+        .unwrap();
+        assert_eq!(dec_rdx_vec(&ct_ck, &ck), OFB_CIPHERTEXT[..len]);
+    }
+
+    #[test]
+    fn test_pkcs7() {
+        for len in 0..=33 {
+            let data: Vec<u8> = (0..len as u8).collect();
+            let padded = pkcs7_pad(&data);
+
+            assert!(padded.len().is_multiple_of(16) && padded.len() > data.len());
+            assert_eq!(pkcs7_unpad(&padded), Some(data.as_slice()));
+        }
+
+        assert_eq!(pkcs7_unpad(&[0u8; 16]), None);
+        assert_eq!(pkcs7_unpad(&[17u8; 16]), None);
+        assert_eq!(pkcs7_unpad(&[3u8; 15]), None);
+    }
+
+    #[test]
+    fn test_cbc_encrypt() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&SP800_38A_KEY), &ck).unwrap();
+        let iv_ck = enc_state_fhe(&SP800_38A_IV, &ck);
+
+        let pt_ck = enc_rdx_vec(&SP800_38A_PLAINTEXT[..32], &ck);
+        let ct_ck = cbc_encrypt_fhe(&xk_ck, &iv_ck, &pt_ck, &sk).unwrap();
+
+        assert_eq!(dec_rdx_vec(&ct_ck, &ck), CBC_CIPHERTEXT[..32]);
+    }
+
+    #[test]
+    fn test_cbc_decrypt() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&SP800_38A_KEY), &ck).unwrap();
+        let iv_ck = enc_state_fhe(&SP800_38A_IV, &ck);
+
+        let ct_ck = enc_rdx_vec(&CBC_CIPHERTEXT[..32], &ck);
+        let pt_ck = cbc_decrypt_fhe(&xk_ck, &iv_ck, &ct_ck, &sk).unwrap();
+
+        assert_eq!(dec_rdx_vec(&pt_ck, &ck), SP800_38A_PLAINTEXT[..32]);
+    }
+}
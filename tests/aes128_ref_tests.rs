@@ -0,0 +1,162 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{dec_state_fhe, enc_state_fhe, gen_rdx_keys, parse_hex};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::PosVals;
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::{KeySize, expand_key, key_expansion};
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{
+    add_round_key_fhe, inv_mix_columns_bc_fhe, inv_mix_columns_fhe, inv_shift_rows_fhe,
+    inv_sub_bytes_fhe, mix_columns_fhe, shift_rows_fhe, sub_bytes_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_ref::{
+    add_round_key, decrypt_state, encrypt_state, encrypt_state_with, inv_mix_columns,
+    inv_shift_rows, inv_sub_bytes, mix_columns, shift_rows, sub_bytes,
+};
+pub use aes128_rdx_bc_fhe::aes128_sbox::SboxCircuit;
+pub use aes128_rdx_bc_fhe::aes128_state::EncryptedState;
+pub use aes128_rdx_bc_fhe::aes128_tables::{GMUL9, GMULB, GMULD, GMULE, gen_tbl};
+
+use aes::Aes128;
+use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
+
+pub type Step = fn(&mut [u8; 16]);
+
+pub fn block(s: &str) -> [u8; 16] {
+    parse_hex(s).unwrap().try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ref_fips197() {
+        let plaintext = block("00112233445566778899aabbccddeeff");
+        let vectors = [
+            (KeySize::Aes128, "69c4e0d86a7b0430d8cdb78070b4c55a"),
+            (KeySize::Aes192, "dda97ca4864cdfe06eaf70a0ec0d7191"),
+            (KeySize::Aes256, "8ea2b7ca516745bfeafc49904b496089"),
+        ];
+
+        for (key_size, ciphertext) in vectors {
+            let key: Vec<u8> = (0..key_size.key_len() as u8).collect();
+            let xk = expand_key(&key, key_size).unwrap();
+
+            let enc = encrypt_state(&plaintext, &xk);
+            assert_eq!(enc, block(ciphertext));
+            assert_eq!(decrypt_state(&enc, &xk), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_ref_vs_aes_crate() {
+        for _ in 0..64 {
+            let key: [u8; 16] = rand::random();
+            let input: [u8; 16] = rand::random();
+
+            let mut expected = GenericArray::from(input);
+            Aes128::new(&GenericArray::from(key)).encrypt_block(&mut expected);
+
+            assert_eq!(encrypt_state(&input, &key_expansion(&key)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_ref_steps() {
+        // FIPS-197 C.1 round 1
+        let xk = key_expansion(&block("000102030405060708090a0b0c0d0e0f"));
+        let mut states = Vec::new();
+        encrypt_state_with(
+            &block("00112233445566778899aabbccddeeff"),
+            &xk,
+            &mut |round, step, state| {
+                if round == 1 {
+                    states.push((step.to_string(), *state));
+                }
+            },
+        );
+
+        let expected = [
+            ("sub_bytes", "63cab7040953d051cd60e0e7ba70e18c"),
+            ("shift_rows", "6353e08c0960e104cd70b751bacad0e7"),
+            ("mix_columns", "5f72641557f5bc92f7be3b291db9f91a"),
+            ("add_round_key", "89d810e8855ace682d1843d8cb128fe4"),
+        ];
+        for ((step, state), (exp_step, exp_state)) in states.iter().zip(expected) {
+            assert_eq!(step, exp_step);
+            assert_eq!(*state, block(exp_state));
+        }
+
+        // every step undone by its inverse
+        let input: [u8; 16] = rand::random();
+        let steps: [(Step, Step); 3] = [
+            (sub_bytes, inv_sub_bytes),
+            (shift_rows, inv_shift_rows),
+            (mix_columns, inv_mix_columns),
+        ];
+        for (step, inv) in steps {
+            let mut state = input;
+            step(&mut state);
+            assert_ne!(state, input);
+            inv(&mut state);
+            assert_eq!(state, input);
+        }
+    }
+
+    #[test]
+    fn test_fhe_steps_vs_ref() {
+        let (ck, sk) = gen_rdx_keys();
+        let pos_vals = PosVals::new_trivial(&sk);
+
+        let input: [u8; 16] = rand::random();
+        let rkey: [u8; 16] = rand::random();
+        let input_ck = enc_state_fhe(&input, &ck);
+        let rkey_ck = enc_state_fhe(&rkey, &ck);
+
+        let check =
+            |name: &str, fhe: &dyn Fn(&mut EncryptedState), plain: &dyn Fn(&mut [u8; 16])| {
+                let mut state_ck = input_ck.clone();
+                fhe(&mut state_ck);
+                let mut state = input;
+                plain(&mut state);
+                println!("{name}");
+                assert_eq!(dec_state_fhe(&state_ck, &ck).unwrap(), state, "{name}");
+            };
+
+        check(
+            "add_round_key",
+            &|s| add_round_key_fhe(s, &rkey_ck, &sk).unwrap(),
+            &|s| add_round_key(s, &rkey),
+        );
+        check(
+            "sub_bytes",
+            &|s| sub_bytes_fhe(s, &SboxCircuit, &pos_vals, &sk),
+            &sub_bytes,
+        );
+        check(
+            "inv_sub_bytes",
+            &|s| inv_sub_bytes_fhe(s, &SboxCircuit, &pos_vals, &sk),
+            &inv_sub_bytes,
+        );
+        check("shift_rows", &|s| shift_rows_fhe(s).unwrap(), &shift_rows);
+        check(
+            "inv_shift_rows",
+            &|s| inv_shift_rows_fhe(s).unwrap(),
+            &inv_shift_rows,
+        );
+        check(
+            "mix_columns",
+            &|s| mix_columns_fhe(s, &pos_vals, &sk).unwrap(),
+            &mix_columns,
+        );
+        check(
+            "inv_mix_columns_bc",
+            &|s| inv_mix_columns_bc_fhe(s, &pos_vals, &sk).unwrap(),
+            &inv_mix_columns,
+        );
+
+        let [g9, gb, gd, ge] = [&GMUL9, &GMULB, &GMULD, &GMULE].map(gen_tbl);
+        check(
+            "inv_mix_columns",
+            &|s| inv_mix_columns_fhe(s, &g9, &gb, &gd, &ge, &sk).unwrap(),
+            &inv_mix_columns,
+        );
+    }
+}
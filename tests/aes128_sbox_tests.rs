@@ -0,0 +1,108 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    dec_state_fhe, enc_rdx_vec, enc_round_keys_fhe, enc_state_fhe, gen_rdx_keys,
+};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::PosVals;
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::{key_expansion, key_expansion_with_fhe};
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{
+    InvMixColumns, decrypt_state_with_fhe, encrypt_state_with_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_sbox::{
+    SboxBackend, SboxCircuit, SboxKind, SboxMatchValues, SboxNibbleLut,
+};
+pub use aes128_rdx_bc_fhe::aes128_tables::{INV_SBOX, SBOX};
+
+use tfhe::integer::{RadixClientKey, ServerKey};
+
+pub const SBOX_INPUTS: [u8; 4] = [0x00, 0x53, 0xa7, 0xff];
+
+pub fn check_backend(backend: &dyn SboxBackend, ck: &RadixClientKey, sk: &ServerKey) {
+    let pos_vals = PosVals::new_trivial(sk);
+
+    for (x, x_ck) in SBOX_INPUTS.iter().zip(enc_rdx_vec(&SBOX_INPUTS, ck)) {
+        let s_ck = backend.sbox(&x_ck, &pos_vals, sk);
+        assert_eq!(ck.decrypt::<u8>(&s_ck), SBOX[*x as usize]);
+
+        let inv_ck = backend.sbox_inv(&s_ck, &pos_vals, sk);
+        assert_eq!(ck.decrypt::<u8>(&inv_ck), *x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inv_sbox_table() {
+        for x in 0..=255u8 {
+            assert_eq!(INV_SBOX[SBOX[x as usize] as usize], x);
+        }
+    }
+
+    #[test]
+    fn test_sbox_kind_from_str() {
+        for kind in [
+            SboxKind::BoolCircuit,
+            SboxKind::MatchValues,
+            SboxKind::NibbleLut,
+        ] {
+            assert_eq!(kind.to_string().parse::<SboxKind>(), Ok(kind));
+        }
+        assert!("lut".parse::<SboxKind>().is_err());
+    }
+
+    #[test]
+    fn test_sbox_circuit_backend() {
+        let (ck, sk) = gen_rdx_keys();
+        check_backend(&SboxCircuit, &ck, &sk);
+    }
+
+    #[test]
+    fn test_sbox_match_values_backend() {
+        let (ck, sk) = gen_rdx_keys();
+        check_backend(&SboxMatchValues::new(), &ck, &sk);
+    }
+
+    #[test]
+    fn test_sbox_nibble_lut_backend() {
+        let (ck, sk) = gen_rdx_keys();
+        check_backend(&SboxNibbleLut::new(), &ck, &sk);
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_match_values() {
+        // FIPS-197 C.1
+        let plaintext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let key: [u8; 16] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+        let ciphertext: [u8; 16] = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+
+        let (ck, sk) = gen_rdx_keys();
+        let sbox = SboxMatchValues::new();
+
+        let key_ck = enc_rdx_vec(&key, &ck);
+        let (xk_ck, xk_metrics) = key_expansion_with_fhe(&key_ck, &sbox, &sk).unwrap();
+        assert_eq!(xk_metrics.get("sub_word").count, 10);
+        assert_eq!(xk_metrics.total.count, 10 + 40);
+        assert_eq!(
+            dec_state_fhe(&xk_ck[160..], &ck).unwrap()[..],
+            key_expansion(&key)[160..]
+        );
+
+        let state_ck = enc_state_fhe(&plaintext, &ck);
+        let (enc_ck, _) = encrypt_state_with_fhe(&state_ck, &xk_ck, &sbox, &sk).unwrap();
+        assert_eq!(dec_state_fhe(&enc_ck, &ck).unwrap(), ciphertext);
+
+        let (dec_ck, _) =
+            decrypt_state_with_fhe(&enc_ck, &xk_ck, &sbox, InvMixColumns::BoolCircuit, &sk)
+                .unwrap();
+        assert_eq!(dec_state_fhe(&dec_ck, &ck).unwrap(), plaintext);
+    }
+}
@@ -0,0 +1,107 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{dec_rdx_vec, enc_rdx_vec, gen_rdx_keys, load_rdx_vec};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::{KeySize, expand_key, key_expansion};
+pub use aes128_rdx_bc_fhe::aes128_state::{EncryptedKeySchedule, EncryptedState};
+pub use aes128_rdx_bc_fhe::error::AesFheError;
+
+mod common;
+pub use common::test_dir;
+
+use std::fs;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_layout() {
+        let (ck, sk) = gen_rdx_keys();
+        let input: [u8; 16] = core::array::from_fn(|i| i as u8);
+
+        // column-major, byte 4 * col + row
+        let state_ck = EncryptedState::encrypt(&input, &ck);
+        for col in 0..4 {
+            for row in 0..4 {
+                let byte: u8 = ck.decrypt(state_ck.byte(row, col));
+                assert_eq!(byte, (4 * col + row) as u8);
+            }
+            assert_eq!(
+                dec_rdx_vec(state_ck.column(col), &ck),
+                input[4 * col..4 * col + 4]
+            );
+        }
+        assert_eq!(state_ck.decrypt(&ck), input);
+        assert_eq!(EncryptedState::trivial(&input, &sk).decrypt(&ck), input);
+
+        assert!(matches!(
+            EncryptedState::new(enc_rdx_vec(&[0u8; 15], &ck)).unwrap_err(),
+            AesFheError::StateLength {
+                expected: 16,
+                actual: 15
+            }
+        ));
+    }
+
+    #[test]
+    fn test_key_schedule_round_keys() {
+        let (ck, _) = gen_rdx_keys();
+
+        for key_size in [KeySize::Aes128, KeySize::Aes192, KeySize::Aes256] {
+            let key: Vec<u8> = (0..key_size.key_len()).map(|_| rand::random()).collect();
+            let xk = expand_key(&key, key_size).unwrap();
+
+            let xk_ck = EncryptedKeySchedule::encrypt(&xk, &ck).unwrap();
+            assert_eq!(xk_ck.key_size(), key_size);
+            assert_eq!(xk_ck.rounds(), key_size.rounds());
+            for i in 0..=xk_ck.rounds() {
+                assert_eq!(
+                    dec_rdx_vec(xk_ck.round_key(i), &ck),
+                    xk[16 * i..16 * (i + 1)]
+                );
+            }
+            assert_eq!(xk_ck.decrypt(&ck), xk);
+        }
+
+        assert!(matches!(
+            EncryptedKeySchedule::encrypt(&[0u8; 16], &ck).unwrap_err(),
+            AesFheError::KeyLength(16)
+        ));
+    }
+
+    #[test]
+    fn test_state_and_key_schedule_save_load() {
+        let dir = test_dir("state_and_key_schedule");
+        let (ck, _) = gen_rdx_keys();
+
+        let input: [u8; 16] = rand::random();
+        let xk = key_expansion(&rand::random());
+
+        EncryptedState::encrypt(&input, &ck)
+            .save(&dir.join("state.bin"))
+            .unwrap();
+        EncryptedKeySchedule::encrypt(&xk, &ck)
+            .unwrap()
+            .save(&dir.join("xk.bin"))
+            .unwrap();
+
+        let state_ck = EncryptedState::load(&dir.join("state.bin")).unwrap();
+        let xk_ck = EncryptedKeySchedule::load(&dir.join("xk.bin")).unwrap();
+        assert_eq!(state_ck.decrypt(&ck), input);
+        assert_eq!(xk_ck.decrypt(&ck), xk);
+
+        // the same files as save_rdx_vec, a state is not a key schedule
+        assert_eq!(
+            dec_rdx_vec(&load_rdx_vec(&dir.join("state.bin")).unwrap(), &ck),
+            input
+        );
+        assert!(matches!(
+            EncryptedKeySchedule::load(&dir.join("state.bin")).unwrap_err(),
+            AesFheError::Deserialize(_)
+        ));
+        assert!(matches!(
+            EncryptedState::load(&dir.join("xk.bin")).unwrap_err(),
+            AesFheError::Deserialize(_)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,18 @@
-pub use aes128_rdx_bc_fhe::aes_fhe::{enc_rdx_vec, gen_rdx_keys, print_hex_rdx_fhe};
-pub use aes128_rdx_bc_fhe::aes128_bool_circ::{PosVals, mix_cols, sbox_idx, sbox_inv_idx};
-pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
-pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{decrypt_block_fhe, encrypt_block_fhe, sub_bytes_fhe};
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    dec_rdx_vec, dec_state_fhe, enc_rdx_vec, enc_round_keys_fhe, enc_state_fhe, fmt_hex_rdx_fhe,
+    gen_rdx_keys,
+};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::{
+    PosVals, inv_mix_cols, mix_cols, sbox_idx, sbox_inv_idx,
+};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::{
+    KeySize, expand_key, key_expansion, key_expansion_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{
+    InvMixColumns, decrypt_block_fhe, decrypt_state_fhe, decrypt_state_with_fhe, encrypt_block_fhe,
+    encrypt_state_fhe, sub_bytes_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_sbox::SboxCircuit;
 pub use aes128_rdx_bc_fhe::aes128_tables::{GMUL2, GMUL3, SBOX, gen_tbl};
 
 use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2_KS_PBS;
@@ -43,6 +54,61 @@ pub const KEY_TESTS: &[KeyTest] = &[KeyTest {
     ],
 }];
 
+// the 192 and 256 bit keys, for expand_key
+pub const EXPAND_KEY_TESTS: &[KeyTest] = &[
+    // FIPS-197 A.2, A.3
+    KeyTest {
+        key: &[
+            0x8e, 0x73, 0xb0, 0xf7, 0xda, 0x0e, 0x64, 0x52, 0xc8, 0x10, 0xf3, 0x2b, 0x80, 0x90,
+            0x79, 0xe5, 0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b,
+        ],
+        enc: &[
+            0x8e, 0x73, 0xb0, 0xf7, 0xda, 0x0e, 0x64, 0x52, 0xc8, 0x10, 0xf3, 0x2b, 0x80, 0x90,
+            0x79, 0xe5, 0x62, 0xf8, 0xea, 0xd2, 0x52, 0x2c, 0x6b, 0x7b, 0xfe, 0x0c, 0x91, 0xf7,
+            0x24, 0x02, 0xf5, 0xa5, 0xec, 0x12, 0x06, 0x8e, 0x6c, 0x82, 0x7f, 0x6b, 0x0e, 0x7a,
+            0x95, 0xb9, 0x5c, 0x56, 0xfe, 0xc2, 0x4d, 0xb7, 0xb4, 0xbd, 0x69, 0xb5, 0x41, 0x18,
+            0x85, 0xa7, 0x47, 0x96, 0xe9, 0x25, 0x38, 0xfd, 0xe7, 0x5f, 0xad, 0x44, 0xbb, 0x09,
+            0x53, 0x86, 0x48, 0x5a, 0xf0, 0x57, 0x21, 0xef, 0xb1, 0x4f, 0xa4, 0x48, 0xf6, 0xd9,
+            0x4d, 0x6d, 0xce, 0x24, 0xaa, 0x32, 0x63, 0x60, 0x11, 0x3b, 0x30, 0xe6, 0xa2, 0x5e,
+            0x7e, 0xd5, 0x83, 0xb1, 0xcf, 0x9a, 0x27, 0xf9, 0x39, 0x43, 0x6a, 0x94, 0xf7, 0x67,
+            0xc0, 0xa6, 0x94, 0x07, 0xd1, 0x9d, 0xa4, 0xe1, 0xec, 0x17, 0x86, 0xeb, 0x6f, 0xa6,
+            0x49, 0x71, 0x48, 0x5f, 0x70, 0x32, 0x22, 0xcb, 0x87, 0x55, 0xe2, 0x6d, 0x13, 0x52,
+            0x33, 0xf0, 0xb7, 0xb3, 0x40, 0xbe, 0xeb, 0x28, 0x2f, 0x18, 0xa2, 0x59, 0x67, 0x47,
+            0xd2, 0x6b, 0x45, 0x8c, 0x55, 0x3e, 0xa7, 0xe1, 0x46, 0x6c, 0x94, 0x11, 0xf1, 0xdf,
+            0x82, 0x1f, 0x75, 0x0a, 0xad, 0x07, 0xd7, 0x53, 0xca, 0x40, 0x05, 0x38, 0x8f, 0xcc,
+            0x50, 0x06, 0x28, 0x2d, 0x16, 0x6a, 0xbc, 0x3c, 0xe7, 0xb5, 0xe9, 0x8b, 0xa0, 0x6f,
+            0x44, 0x8c, 0x77, 0x3c, 0x8e, 0xcc, 0x72, 0x04, 0x01, 0x00, 0x22, 0x02,
+        ],
+    },
+    KeyTest {
+        key: &[
+            0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe, 0x2b, 0x73, 0xae, 0xf0, 0x85, 0x7d,
+            0x77, 0x81, 0x1f, 0x35, 0x2c, 0x07, 0x3b, 0x61, 0x08, 0xd7, 0x2d, 0x98, 0x10, 0xa3,
+            0x09, 0x14, 0xdf, 0xf4,
+        ],
+        enc: &[
+            0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe, 0x2b, 0x73, 0xae, 0xf0, 0x85, 0x7d,
+            0x77, 0x81, 0x1f, 0x35, 0x2c, 0x07, 0x3b, 0x61, 0x08, 0xd7, 0x2d, 0x98, 0x10, 0xa3,
+            0x09, 0x14, 0xdf, 0xf4, 0x9b, 0xa3, 0x54, 0x11, 0x8e, 0x69, 0x25, 0xaf, 0xa5, 0x1a,
+            0x8b, 0x5f, 0x20, 0x67, 0xfc, 0xde, 0xa8, 0xb0, 0x9c, 0x1a, 0x93, 0xd1, 0x94, 0xcd,
+            0xbe, 0x49, 0x84, 0x6e, 0xb7, 0x5d, 0x5b, 0x9a, 0xd5, 0x9a, 0xec, 0xb8, 0x5b, 0xf3,
+            0xc9, 0x17, 0xfe, 0xe9, 0x42, 0x48, 0xde, 0x8e, 0xbe, 0x96, 0xb5, 0xa9, 0x32, 0x8a,
+            0x26, 0x78, 0xa6, 0x47, 0x98, 0x31, 0x22, 0x29, 0x2f, 0x6c, 0x79, 0xb3, 0x81, 0x2c,
+            0x81, 0xad, 0xda, 0xdf, 0x48, 0xba, 0x24, 0x36, 0x0a, 0xf2, 0xfa, 0xb8, 0xb4, 0x64,
+            0x98, 0xc5, 0xbf, 0xc9, 0xbe, 0xbd, 0x19, 0x8e, 0x26, 0x8c, 0x3b, 0xa7, 0x09, 0xe0,
+            0x42, 0x14, 0x68, 0x00, 0x7b, 0xac, 0xb2, 0xdf, 0x33, 0x16, 0x96, 0xe9, 0x39, 0xe4,
+            0x6c, 0x51, 0x8d, 0x80, 0xc8, 0x14, 0xe2, 0x04, 0x76, 0xa9, 0xfb, 0x8a, 0x50, 0x25,
+            0xc0, 0x2d, 0x59, 0xc5, 0x82, 0x39, 0xde, 0x13, 0x69, 0x67, 0x6c, 0xcc, 0x5a, 0x71,
+            0xfa, 0x25, 0x63, 0x95, 0x96, 0x74, 0xee, 0x15, 0x58, 0x86, 0xca, 0x5d, 0x2e, 0x2f,
+            0x31, 0xd7, 0x7e, 0x0a, 0xf1, 0xfa, 0x27, 0xcf, 0x73, 0xc3, 0x74, 0x9c, 0x47, 0xab,
+            0x18, 0x50, 0x1d, 0xda, 0xe2, 0x75, 0x7e, 0x4f, 0x74, 0x01, 0x90, 0x5a, 0xca, 0xfa,
+            0xaa, 0xe3, 0xe4, 0xd5, 0x9b, 0x34, 0x9a, 0xdf, 0x6a, 0xce, 0xbd, 0x10, 0x19, 0x0d,
+            0xfe, 0x48, 0x90, 0xd1, 0xe6, 0x18, 0x8d, 0x0b, 0x04, 0x6d, 0xf3, 0x44, 0x70, 0x6c,
+            0x63, 0x1e,
+        ],
+    },
+];
+
 const SBOX_INV: [u8; 256] = [
     0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
     0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
@@ -64,7 +130,7 @@ const SBOX_INV: [u8; 256] = [
 
 #[cfg(test)]
 mod tests {
-    use aes128_rdx_bc_fhe::aes_fhe::NUM_BLOCK;
+    use aes128_rdx_bc_fhe::aes_fhe::DEFAULT_NUM_BLOCK;
 
     use super::*;
 
@@ -87,6 +153,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_expand_key() {
+        for (i, test) in KEY_TESTS.iter().chain(EXPAND_KEY_TESTS).enumerate() {
+            let key_size =
+                KeySize::from_key_len(test.key.len()).expect("Key must be 16, 24 or 32 bytes");
+            let xk = expand_key(test.key, key_size).unwrap();
+
+            for (j, &v) in xk.iter().enumerate() {
+                assert_eq!(
+                    v, test.enc[j],
+                    "key {}: enc[{}] = {:#x}, want {:#x}",
+                    i, j, v, test.enc[j]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_expansion_fhe() {
+        let (ck, sk) = gen_rdx_keys();
+
+        for (i, test) in KEY_TESTS.iter().chain(EXPAND_KEY_TESTS).enumerate() {
+            let key_ck = enc_rdx_vec(test.key, &ck);
+            let xk_ck = key_expansion_fhe(&key_ck, &sk).unwrap();
+            let xk = dec_rdx_vec(&xk_ck, &ck);
+
+            for (j, &v) in xk.iter().enumerate() {
+                assert_eq!(
+                    v, test.enc[j],
+                    "key {}: enc[{}] = {:#x}, want {:#x}",
+                    i, j, v, test.enc[j]
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_encrypt_block_tfhe1() {
         let plaintext: [u8; 16] = [
@@ -105,7 +207,7 @@ mod tests {
         let xk = key_expansion(&key);
 
         let mut dst = [0u8; 16];
-        encrypt_block_fhe(&plaintext, &xk, &mut dst, 1);
+        encrypt_block_fhe(&plaintext, &xk, &mut dst, 1).unwrap();
 
         assert_eq!(
             dst, expected_ciphertext,
@@ -132,7 +234,7 @@ mod tests {
         let xk = key_expansion(&key);
 
         let mut dst = [0u8; 16];
-        decrypt_block_fhe(&ciphertext, &xk, &mut dst, 1);
+        decrypt_block_fhe(&ciphertext, &xk, &mut dst, 1).unwrap();
 
         assert_eq!(
             dst, expected_plaintext,
@@ -159,7 +261,7 @@ mod tests {
         let xk = key_expansion(&key);
 
         let mut dst = [0u8; 16];
-        encrypt_block_fhe(&plaintext, &xk, &mut dst, 1);
+        encrypt_block_fhe(&plaintext, &xk, &mut dst, 1).unwrap();
 
         assert_eq!(
             dst, expected_ciphertext,
@@ -186,7 +288,7 @@ mod tests {
         let xk = key_expansion(&key);
 
         let mut dst = [0u8; 16];
-        decrypt_block_fhe(&ciphertext, &xk, &mut dst, 1);
+        decrypt_block_fhe(&ciphertext, &xk, &mut dst, 1).unwrap();
 
         assert_eq!(
             dst, expected_plaintext,
@@ -195,6 +297,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encrypt_decrypt_block_aes192() {
+        let plaintext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let key: [u8; 24] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+        ];
+        // FIPS-197 C.2
+        let expected_ciphertext: [u8; 16] = [
+            0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0, 0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d,
+            0x71, 0x91,
+        ];
+
+        let xk = expand_key(&key, KeySize::Aes192).unwrap();
+
+        let mut dst = [0u8; 16];
+        encrypt_block_fhe(&plaintext, &xk, &mut dst, 1).unwrap();
+        assert_eq!(dst, expected_ciphertext);
+
+        let mut out = [0u8; 16];
+        decrypt_block_fhe(&dst, &xk, &mut out, 1).unwrap();
+        assert_eq!(out, plaintext);
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_block_aes256() {
+        let plaintext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let key: [u8; 32] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ];
+        // FIPS-197 C.3
+        let expected_ciphertext: [u8; 16] = [
+            0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49,
+            0x60, 0x89,
+        ];
+
+        let xk = expand_key(&key, KeySize::Aes256).unwrap();
+
+        let mut dst = [0u8; 16];
+        encrypt_block_fhe(&plaintext, &xk, &mut dst, 1).unwrap();
+        assert_eq!(dst, expected_ciphertext);
+
+        let mut out = [0u8; 16];
+        decrypt_block_fhe(&dst, &xk, &mut out, 1).unwrap();
+        assert_eq!(out, plaintext);
+    }
+
     #[test]
     fn test_encrypt_decrypt_rnd_block() {
         let mut key = [0u8; 16];
@@ -209,7 +366,7 @@ mod tests {
 
         let xk = key_expansion(&key);
         let mut dst = [0u8; 16];
-        encrypt_block_fhe(&iv, &xk, &mut dst, 1);
+        encrypt_block_fhe(&iv, &xk, &mut dst, 1).unwrap();
 
         assert_eq!(
             GenericArray::from(dst),
@@ -220,7 +377,7 @@ mod tests {
         );
 
         let mut out = [0u8; 16];
-        decrypt_block_fhe(&dst, &xk, &mut out, 1);
+        decrypt_block_fhe(&dst, &xk, &mut out, 1).unwrap();
 
         assert_eq!(
             out, iv,
@@ -229,27 +386,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_client_server_block() {
+        let plaintext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let key: [u8; 16] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+        let expected_ciphertext: [u8; 16] = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+
+        // client
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&key), &ck).unwrap();
+        let state_ck = enc_state_fhe(&plaintext, &ck);
+
+        // server
+        let enc_ck = encrypt_state_fhe(&state_ck, &xk_ck, &sk).unwrap();
+        let dec_ck = decrypt_state_fhe(&enc_ck, &xk_ck, &sk).unwrap();
+
+        // client
+        assert_eq!(dec_state_fhe(&enc_ck, &ck).unwrap(), expected_ciphertext);
+        assert_eq!(dec_state_fhe(&dec_ck, &ck).unwrap(), plaintext);
+    }
+
     #[test]
     fn test_perf_rdx_xor() {
         let (ck, sk) = gen_rdx_keys();
 
         let state = vec![0xfe, 0xff]; // byte to rdx
         let mut state_ck = enc_rdx_vec(&state, &ck);
-        print_hex_rdx_fhe("state_ck      ", 0, &state_ck, &ck);
+        println!("{}", fmt_hex_rdx_fhe("state_ck      ", 0, &state_ck, &ck));
 
         let start = Instant::now();
         for _ in 1..10 {
             let tmp = state_ck.to_vec();
             state_ck[1] = sk.unchecked_bitxor(&tmp[0], &tmp[1]);
             state_ck[0] = sk.unchecked_bitxor(&tmp[1], &tmp[0]);
-            print_hex_rdx_fhe("rdx bitxor", 0, &state_ck, &ck);
+            println!("{}", fmt_hex_rdx_fhe("rdx bitxor", 0, &state_ck, &ck));
         }
         println!(
             "test_perf_rdx_xor {:.?}",
             start.elapsed().checked_div(2 * 10)
         );
 
-        print_hex_rdx_fhe("rdx bitxor", 0, &state_ck, &ck);
+        println!("{}", fmt_hex_rdx_fhe("rdx bitxor", 0, &state_ck, &ck));
     }
 
     // https://github.com/zama-ai/tfhe-rs/issues/816
@@ -260,11 +446,14 @@ mod tests {
 
         let start = Instant::now();
         let state_ck: [BaseRadixCiphertext<Ciphertext>; 16] =
-            core::array::from_fn(|_| sk.create_trivial_radix(0, NUM_BLOCK));
+            core::array::from_fn(|_| sk.create_trivial_radix(0, DEFAULT_NUM_BLOCK));
         println!("test_init_arr_ciphertext  {:.?}", start.elapsed());
         assert!(state_ck.len() == 16);
 
-        print_hex_rdx_fhe("init arr ciphertext", 0, &state_ck.to_vec(), &ck);
+        println!(
+            "{}",
+            fmt_hex_rdx_fhe("init arr ciphertext", 0, &state_ck, &ck)
+        );
     }
 
     #[test]
@@ -273,50 +462,53 @@ mod tests {
 
         let start = Instant::now();
         let state_ck: Vec<BaseRadixCiphertext<Ciphertext>> = (0..16)
-            .map(|_| sk.create_trivial_radix(0, NUM_BLOCK))
+            .map(|_| sk.create_trivial_radix(0, DEFAULT_NUM_BLOCK))
             .collect();
 
         println!("test_init_vec_ciphertext  {:.?}", start.elapsed());
         assert!(state_ck.len() == 16);
 
-        print_hex_rdx_fhe("init vec ciphertext", 0, &state_ck, &ck);
+        println!(
+            "{}",
+            fmt_hex_rdx_fhe("init vec ciphertext", 0, &state_ck, &ck)
+        );
     }
 
     #[test]
     fn test_sbox() {
-        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCK);
+        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, DEFAULT_NUM_BLOCK);
         let pos_vals = PosVals::new(&ck);
 
-        for i in 0..255 {
+        for (i, &expected) in SBOX.iter().enumerate().take(255) {
             let idx = ck.encrypt(i as u8);
 
             let sbox = sbox_idx(&idx, &pos_vals, &sk);
             let sbox_val = ck.decrypt::<u8>(&sbox);
 
             println!("i {:},  result {:x}", i, sbox_val);
-            assert!(sbox_val == SBOX[i]);
+            assert!(sbox_val == expected);
         }
     }
 
     #[test]
     fn test_sbox_inv() {
-        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCK);
+        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, DEFAULT_NUM_BLOCK);
         let pos_vals = PosVals::new(&ck);
 
-        for i in 0..255 {
+        for (i, &expected) in SBOX_INV.iter().enumerate().take(255) {
             let idx = ck.encrypt(i as u8);
 
             let sbox = sbox_inv_idx(&idx, &pos_vals, &sk);
             let sbox_val = ck.decrypt::<u8>(&sbox);
 
             println!("i {:},  result {:x}", i, sbox_val);
-            assert!(sbox_val == SBOX_INV[i]);
+            assert!(sbox_val == expected);
         }
     }
 
     #[test]
     fn test_mix_cols_1() {
-        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCK);
+        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, DEFAULT_NUM_BLOCK);
         let pos_vals = PosVals::new(&ck);
 
         let c1_pre = [0x01, 0x01, 0x01, 0x01];
@@ -346,7 +538,7 @@ mod tests {
 
     #[test]
     fn test_mix_cols_2() {
-        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCK);
+        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, DEFAULT_NUM_BLOCK);
         let pos_vals = PosVals::new(&ck);
 
         let c1_pre = [0xc6, 0xc6, 0xc6, 0xc6];
@@ -376,7 +568,7 @@ mod tests {
 
     #[test]
     fn test_mix_cols_3() {
-        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCK);
+        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, DEFAULT_NUM_BLOCK);
         let pos_vals = PosVals::new(&ck);
 
         let c1_pre = [0xd4, 0xbf, 0x5d, 0x30];
@@ -406,7 +598,7 @@ mod tests {
 
     #[test]
     fn test_mix_cols_4() {
-        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCK);
+        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, DEFAULT_NUM_BLOCK);
         let pos_vals = PosVals::new(&ck);
 
         let c1_pre = [0xe0, 0xb4, 0x52, 0xae];
@@ -433,4 +625,70 @@ mod tests {
             assert_eq!(post, c1_pst[i]);
         }
     }
+
+    #[test]
+    fn test_inv_mix_cols_1() {
+        let (ck, sk) = gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, DEFAULT_NUM_BLOCK);
+        let pos_vals = PosVals::new(&ck);
+
+        let c1_pre = [0x8e, 0x4d, 0xa1, 0xbc];
+        let c1_pst = [0xdb, 0x13, 0x53, 0x45];
+
+        let col = [
+            ck.encrypt(c1_pre[0] as u8),
+            ck.encrypt(c1_pre[1] as u8),
+            ck.encrypt(c1_pre[2] as u8),
+            ck.encrypt(c1_pre[3] as u8),
+        ];
+
+        let out = inv_mix_cols(&col, &pos_vals, &sk);
+
+        let dec = [
+            ck.decrypt::<u8>(&out[0]),
+            ck.decrypt::<u8>(&out[1]),
+            ck.decrypt::<u8>(&out[2]),
+            ck.decrypt::<u8>(&out[3]),
+        ];
+
+        for (i, (&pre, &post)) in c1_pre.iter().zip(dec.iter()).enumerate() {
+            println!("r{}_in {:x}, r{}_out {:x}", i + 1, pre, i + 1, post);
+            assert_eq!(post, c1_pst[i]);
+        }
+    }
+
+    #[test]
+    fn test_decrypt_state_inv_mix_bc() {
+        let ciphertext: [u8; 16] = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        let key: [u8; 16] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+        let plaintext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&key), &ck).unwrap();
+        let state_ck = enc_state_fhe(&ciphertext, &ck);
+
+        let (dec_ck, metrics) = decrypt_state_with_fhe(
+            &state_ck,
+            &xk_ck,
+            &SboxCircuit,
+            InvMixColumns::BoolCircuit,
+            &sk,
+        )
+        .unwrap();
+
+        assert_eq!(dec_state_fhe(&dec_ck, &ck).unwrap(), plaintext);
+        assert_eq!(metrics.rounds.len(), 11);
+        assert_eq!(metrics.get("inv_mix_columns").count, 9);
+        assert_eq!(metrics.get("add_round_key").count, 11);
+        assert!(!metrics.ops.contains_key("gen_tbl"));
+        println!("{}", metrics);
+    }
 }
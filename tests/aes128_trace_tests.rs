@@ -0,0 +1,124 @@
+#![cfg(feature = "debug-trace")]
+
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    dec_state_fhe, enc_round_keys_fhe, enc_state_fhe, gen_rdx_keys, parse_hex,
+};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::InvMixColumns;
+pub use aes128_rdx_bc_fhe::aes128_sbox::SboxCircuit;
+pub use aes128_rdx_bc_fhe::aes128_trace::{
+    Tracer, decrypt_reference, decrypt_state_traced_fhe, encrypt_reference,
+    encrypt_state_traced_fhe, first_divergence,
+};
+
+// FIPS-197 C.1
+pub const PLAINTEXT: [u8; 16] = [
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+];
+pub const KEY: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+pub const CIPHERTEXT: [u8; 16] = [
+    0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_trace() {
+        let xk = key_expansion(&KEY);
+        let trace = encrypt_reference(&PLAINTEXT, &xk);
+
+        // 1 + 9 * 4 + 3 steps
+        assert_eq!(trace.len(), 40);
+        assert_eq!(trace.last().unwrap().state, CIPHERTEXT);
+
+        // FIPS-197 C.1 round 1
+        let round_1: Vec<_> = trace.iter().filter(|s| s.round == 1).collect();
+        assert_eq!(
+            parse_hex("63cab7040953d051cd60e0e7ba70e18c").unwrap(),
+            round_1[0].state
+        );
+        assert_eq!(
+            parse_hex("6353e08c0960e104cd70b751bacad0e7").unwrap(),
+            round_1[1].state
+        );
+        assert_eq!(
+            parse_hex("5f72641557f5bc92f7be3b291db9f91a").unwrap(),
+            round_1[2].state
+        );
+        assert_eq!(
+            parse_hex("89d810e8855ace682d1843d8cb128fe4").unwrap(),
+            round_1[3].state
+        );
+
+        let trace = decrypt_reference(&CIPHERTEXT, &xk);
+        assert_eq!(trace.len(), 40);
+        assert_eq!(trace.last().unwrap().state, PLAINTEXT);
+        // FIPS-197 C.1 round[ 1].iinput
+        assert_eq!(
+            parse_hex("7ad5fda789ef4e272bca100b3d9ff59f").unwrap(),
+            trace[0].state
+        );
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let xk = key_expansion(&KEY);
+        let reference = encrypt_reference(&PLAINTEXT, &xk);
+
+        let mut trace = reference.clone();
+        assert_eq!(first_divergence(&trace, &reference), None);
+
+        trace[10].state[3] ^= 1;
+        let div = first_divergence(&trace, &reference).unwrap();
+        assert_eq!((div.round, div.step.as_str()), (3, "shift_rows"));
+        assert_eq!(div.actual, Some(trace[10].state));
+        println!("{}", div);
+
+        trace.truncate(5);
+        let div = first_divergence(&trace, &reference).unwrap();
+        assert_eq!(
+            (div.round, div.step.as_str(), div.actual),
+            (2, "sub_bytes", None)
+        );
+    }
+
+    #[test]
+    fn test_trace_encrypt_decrypt_fhe() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk = key_expansion(&KEY);
+        let xk_ck = enc_round_keys_fhe(&xk, &ck).unwrap();
+
+        let mut observed = 0;
+        let mut tracer = Tracer::new(&ck).with_observer(|step| {
+            println!("{}", step);
+            observed += 1;
+        });
+        let state_ck = enc_state_fhe(&PLAINTEXT, &ck);
+        let (enc_ck, _) =
+            encrypt_state_traced_fhe(&state_ck, &xk_ck, &SboxCircuit, &mut tracer, &sk).unwrap();
+
+        let reference = encrypt_reference(&PLAINTEXT, &xk);
+        assert_eq!(first_divergence(tracer.steps(), &reference), None);
+        drop(tracer);
+        assert_eq!(observed, reference.len());
+
+        let mut tracer = Tracer::new(&ck);
+        let (dec_ck, _) = decrypt_state_traced_fhe(
+            &enc_ck,
+            &xk_ck,
+            &SboxCircuit,
+            InvMixColumns::BoolCircuit,
+            &mut tracer,
+            &sk,
+        )
+        .unwrap();
+
+        let reference = decrypt_reference(&CIPHERTEXT, &xk);
+        assert_eq!(first_divergence(tracer.steps(), &reference), None);
+        assert_eq!(dec_state_fhe(&dec_ck, &ck).unwrap(), PLAINTEXT);
+    }
+}
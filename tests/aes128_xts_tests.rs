@@ -0,0 +1,82 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{dec_rdx_vec, enc_rdx_vec, enc_round_keys_fhe, gen_rdx_keys};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+pub use aes128_rdx_bc_fhe::aes128_xts::{xts_decrypt_fhe, xts_encrypt_fhe};
+
+pub struct XtsTest {
+    pub key1: [u8; 16],
+    pub key2: [u8; 16],
+    pub sector: u128,
+    pub ptx: [u8; 32],
+    pub ctx: [u8; 32],
+}
+
+// IEEE 1619-2007 Annex B, vectors 1 and 2
+pub const XTS_TESTS: &[XtsTest] = &[
+    XtsTest {
+        key1: [0x00; 16],
+        key2: [0x00; 16],
+        sector: 0,
+        ptx: [0x00; 32],
+        ctx: [
+            0x91, 0x7c, 0xf6, 0x9e, 0xbd, 0x68, 0xb2, 0xec, 0x9b, 0x9f, 0xe9, 0xa3, 0xea, 0xdd,
+            0xa6, 0x92, 0xcd, 0x43, 0xd2, 0xf5, 0x95, 0x98, 0xed, 0x85, 0x8c, 0x02, 0xc2, 0x65,
+            0x2f, 0xbf, 0x92, 0x2e,
+        ],
+    },
+    XtsTest {
+        key1: [0x11; 16],
+        key2: [0x22; 16],
+        sector: 0x3333333333,
+        ptx: [0x44; 32],
+        ctx: [
+            0xc4, 0x54, 0x18, 0x5e, 0x6a, 0x16, 0x93, 0x6e, 0x39, 0x33, 0x40, 0x38, 0xac, 0xef,
+            0x83, 0x8b, 0xfb, 0x18, 0x6f, 0xff, 0x74, 0x80, 0xad, 0xc4, 0x28, 0x93, 0x82, 0xec,
+            0xd6, 0xd3, 0x94, 0xf0,
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xts_encrypt() {
+        let (ck, sk) = gen_rdx_keys();
+
+        for (i, test) in XTS_TESTS.iter().enumerate() {
+            let xk1_ck = enc_round_keys_fhe(&key_expansion(&test.key1), &ck).unwrap();
+            let xk2_ck = enc_round_keys_fhe(&key_expansion(&test.key2), &ck).unwrap();
+
+            let ptx_ck = enc_rdx_vec(&test.ptx, &ck);
+            let ctx_ck = xts_encrypt_fhe(&xk1_ck, &xk2_ck, test.sector, &ptx_ck, &sk).unwrap();
+
+            assert_eq!(
+                dec_rdx_vec(&ctx_ck, &ck),
+                test.ctx,
+                "xts vector {} failed",
+                i + 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_xts_decrypt() {
+        let (ck, sk) = gen_rdx_keys();
+
+        for (i, test) in XTS_TESTS.iter().enumerate() {
+            let xk1_ck = enc_round_keys_fhe(&key_expansion(&test.key1), &ck).unwrap();
+            let xk2_ck = enc_round_keys_fhe(&key_expansion(&test.key2), &ck).unwrap();
+
+            let ctx_ck = enc_rdx_vec(&test.ctx, &ck);
+            let ptx_ck = xts_decrypt_fhe(&xk1_ck, &xk2_ck, test.sector, &ctx_ck, &sk).unwrap();
+
+            assert_eq!(
+                dec_rdx_vec(&ptx_ck, &ck),
+                test.ptx,
+                "xts vector {} failed",
+                i + 1
+            );
+        }
+    }
+}
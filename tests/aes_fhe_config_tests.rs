@@ -0,0 +1,129 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    DEFAULT_NUM_BLOCK, FheConfig, ParamSet, enc_rdx_vec, gen_rdx_keys_with, num_blocks,
+};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::{
+    PosVals, get_bits_from_block, get_block_from_bits, inv_mix_cols, mix_cols,
+};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{
+    add_round_key_fhe, decrypt_state_fhe, encrypt_state_fhe, sub_bytes_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_sbox::{SboxBackend, SboxCircuit, SboxMatchValues};
+pub use aes128_rdx_bc_fhe::aes128_state::{EncryptedKeySchedule, EncryptedState};
+pub use aes128_rdx_bc_fhe::aes128_tables::SBOX;
+
+use tfhe::integer::RadixClientKey;
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::shortint::Ciphertext;
+
+pub const BYTES: [u8; 4] = [0xdb, 0x13, 0x53, 0x45];
+pub const MIXED: [u8; 4] = [0x8e, 0x4d, 0xa1, 0xbc];
+
+// FIPS-197 appendix C.1
+pub const FIPS197_KEY: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+pub const FIPS197_PLAIN: [u8; 16] = [
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+];
+pub const FIPS197_CIPHER: [u8; 16] = [
+    0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a,
+];
+
+pub fn dec_bytes(cts: &[BaseRadixCiphertext<Ciphertext>], ck: &RadixClientKey) -> Vec<u8> {
+    cts.iter().map(|ct| ck.decrypt::<u8>(ct)).collect()
+}
+
+// the building blocks of both pipelines on one column
+pub fn check_config(config: FheConfig) {
+    let (ck, sk) = gen_rdx_keys_with(&config);
+    assert!(config.matches(&sk));
+    assert_eq!(num_blocks(&sk), config.num_blocks());
+    assert_eq!(ck.num_blocks(), config.num_blocks());
+
+    let pos_vals = PosVals::new_trivial(&sk);
+    let col = enc_rdx_vec(&BYTES, &ck);
+
+    let mut state = col.clone();
+    add_round_key_fhe(&mut state, &col, &sk).unwrap();
+    assert_eq!(dec_bytes(&state, &ck), vec![0u8; 4]);
+
+    let expected: Vec<u8> = BYTES.iter().map(|&x| SBOX[x as usize]).collect();
+    let backends: [&dyn SboxBackend; 2] = [&SboxCircuit, &SboxMatchValues::new()];
+    for backend in backends {
+        let mut state = col.clone();
+        sub_bytes_fhe(&mut state, backend, &pos_vals, &sk);
+        assert_eq!(dec_bytes(&state, &ck), expected);
+    }
+
+    let col: [_; 4] = col.try_into().unwrap();
+    let mixed = mix_cols(&col, &pos_vals, &sk);
+    assert_eq!(dec_bytes(&mixed, &ck), MIXED);
+
+    let unmixed = inv_mix_cols(&mixed, &pos_vals, &sk);
+    assert_eq!(dec_bytes(&unmixed, &ck), BYTES);
+
+    let bits = get_bits_from_block(&col, &pos_vals, &sk);
+    let bytes = get_block_from_bits(&bits, &pos_vals, &sk).unwrap();
+    assert_eq!(dec_bytes(&bytes, &ck), BYTES);
+}
+
+// the whole cipher and inverse cipher, one block
+pub fn check_fips197(config: FheConfig) {
+    let (ck, sk) = gen_rdx_keys_with(&config);
+    let xk_ck = EncryptedKeySchedule::encrypt(&key_expansion(&FIPS197_KEY), &ck).unwrap();
+
+    let state_ck = EncryptedState::encrypt(&FIPS197_PLAIN, &ck);
+    let cipher_ck = encrypt_state_fhe(&state_ck, &xk_ck, &sk).unwrap();
+    assert_eq!(cipher_ck.decrypt(&ck), FIPS197_CIPHER);
+
+    let plain_ck = decrypt_state_fhe(&cipher_ck, &xk_ck, &sk).unwrap();
+    assert_eq!(plain_ck.decrypt(&ck), FIPS197_PLAIN);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param_set_from_str() {
+        for s in ["1_1", "2_2", "4_4"] {
+            assert_eq!(s.parse::<ParamSet>().unwrap().to_string(), s);
+        }
+        assert!("3_3".parse::<ParamSet>().is_err());
+
+        assert_eq!(FheConfig::default().num_blocks(), DEFAULT_NUM_BLOCK);
+        assert_eq!(FheConfig::new(ParamSet::Message1Carry1).num_blocks(), 8);
+        assert_eq!(FheConfig::new(ParamSet::Message4Carry4).num_blocks(), 2);
+    }
+
+    #[test]
+    fn test_config_message_1_carry_1() {
+        check_config(FheConfig::new(ParamSet::Message1Carry1));
+    }
+
+    #[test]
+    fn test_config_message_2_carry_2() {
+        check_config(FheConfig::new(ParamSet::Message2Carry2));
+    }
+
+    #[test]
+    fn test_config_message_4_carry_4() {
+        check_config(FheConfig::new(ParamSet::Message4Carry4));
+    }
+
+    #[test]
+    fn test_fips197_message_1_carry_1() {
+        check_fips197(FheConfig::new(ParamSet::Message1Carry1));
+    }
+
+    #[test]
+    fn test_fips197_message_2_carry_2() {
+        check_fips197(FheConfig::new(ParamSet::Message2Carry2));
+    }
+
+    #[test]
+    fn test_fips197_message_4_carry_4() {
+        check_fips197(FheConfig::new(ParamSet::Message4Carry4));
+    }
+}
@@ -0,0 +1,181 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    FheConfig, ParamSet, check_params, dec_state_fhe, enc_rdx_vec, enc_round_keys_fhe,
+    gen_rdx_keys, load_rdx_vec, load_server_key,
+};
+pub use aes128_rdx_bc_fhe::aes128_bitsliced::{encrypt_bits_bc, encrypt_state_bitsliced_fhe};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::{PosVals, byte_circuit_idx, get_block_from_bits};
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::{
+    KeySize, expand_key, key_expansion, key_expansion_fhe,
+};
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{add_round_key_fhe, encrypt_state_fhe, shift_rows_fhe};
+pub use aes128_rdx_bc_fhe::aes128_state::{EncryptedKeySchedule, EncryptedState};
+pub use aes128_rdx_bc_fhe::bool_circ::{BoolCircuit, wire_names};
+pub use aes128_rdx_bc_fhe::error::AesFheError;
+pub use aes128_rdx_bc_fhe::metrics::Metrics;
+
+mod common;
+pub use common::test_dir;
+
+use tfhe::integer::ServerKey;
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::shortint::Ciphertext;
+
+use std::fs;
+
+pub fn trivial_state(
+    n: usize,
+    num_blocks: usize,
+    sk: &ServerKey,
+) -> Vec<BaseRadixCiphertext<Ciphertext>> {
+    (0..n)
+        .map(|i| sk.create_trivial_radix(i as u8, num_blocks))
+        .collect()
+}
+
+pub fn is_state_length(err: AesFheError, expected: usize, actual: usize) -> bool {
+    matches!(err, AesFheError::StateLength { expected: e, actual: a } if (e, a) == (expected, actual))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_state_and_key_length() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&[0u8; 16]), &ck).unwrap();
+
+        let mut state = trivial_state(20, 4, &sk);
+        assert!(is_state_length(
+            shift_rows_fhe(&mut state).unwrap_err(),
+            32,
+            20
+        ));
+        assert!(is_state_length(
+            dec_state_fhe(&state, &ck).unwrap_err(),
+            16,
+            20
+        ));
+        assert!(is_state_length(
+            EncryptedState::new(state.clone()).unwrap_err(),
+            16,
+            20
+        ));
+
+        let pos_vals = PosVals::new_trivial(&sk);
+        let bits: Vec<_> = (0..12)
+            .map(|_| sk.create_trivial_boolean_block(false))
+            .collect();
+        assert!(matches!(
+            get_block_from_bits(&bits, &pos_vals, &sk).unwrap_err(),
+            AesFheError::BitLength {
+                expected: 16,
+                actual: 12
+            }
+        ));
+
+        let mut state = trivial_state(16, 4, &sk);
+        assert!(matches!(
+            expand_key(&[0u8; 16], KeySize::Aes256).unwrap_err(),
+            AesFheError::KeyLength(16)
+        ));
+        assert!(matches!(
+            enc_round_keys_fhe(&[0u8; 100], &ck).unwrap_err(),
+            AesFheError::KeyLength(100)
+        ));
+        assert!(matches!(
+            EncryptedKeySchedule::new(xk_ck[..17].to_vec()).unwrap_err(),
+            AesFheError::KeyLength(17)
+        ));
+        assert!(matches!(
+            add_round_key_fhe(&mut state, &xk_ck[..4], &sk).unwrap_err(),
+            AesFheError::RoundKeyLength(4)
+        ));
+
+        // the bitsliced rounds check the same before any bootstrap
+        let bits = vec![core::array::from_fn(|_| sk.create_trivial_boolean_block(false)); 16];
+        assert!(matches!(
+            encrypt_bits_bc(&bits, &bits[..], &mut Metrics::new(), &sk).unwrap_err(),
+            AesFheError::KeyLength(16)
+        ));
+        assert!(is_state_length(
+            encrypt_bits_bc(
+                &bits[..12],
+                &vec![bits[0].clone(); 176],
+                &mut Metrics::new(),
+                &sk
+            )
+            .unwrap_err(),
+            16,
+            12
+        ));
+    }
+
+    #[test]
+    fn test_error_param_mismatch() {
+        let (ck, sk) = gen_rdx_keys();
+        let xk_ck = enc_round_keys_fhe(&key_expansion(&[0u8; 16]), &ck).unwrap();
+
+        assert!(FheConfig::default().check(&sk).is_ok());
+        assert_eq!(FheConfig::of_server_key(&sk).unwrap(), FheConfig::default());
+        assert!(matches!(
+            FheConfig::new(ParamSet::Message4Carry4)
+                .check(&sk)
+                .unwrap_err(),
+            AesFheError::ParamMismatch(_)
+        ));
+
+        // bytes of 8 blocks do not belong to a 2_2 server key
+        let state = EncryptedState::new(trivial_state(16, 8, &sk)).unwrap();
+        assert!(check_params(&enc_rdx_vec(&[0u8; 16], &ck), &sk).is_ok());
+        assert!(matches!(
+            check_params(&state, &sk).unwrap_err(),
+            AesFheError::ParamMismatch(_)
+        ));
+        assert!(matches!(
+            encrypt_state_fhe(&state, &xk_ck, &sk).unwrap_err(),
+            AesFheError::ParamMismatch(_)
+        ));
+        assert!(matches!(
+            key_expansion_fhe(&state, &sk).unwrap_err(),
+            AesFheError::ParamMismatch(_)
+        ));
+        assert!(matches!(
+            encrypt_state_bitsliced_fhe(&state, &xk_ck, &sk).unwrap_err(),
+            AesFheError::ParamMismatch(_)
+        ));
+    }
+
+    #[test]
+    fn test_error_circuit_and_deserialize() {
+        let (ck, sk) = gen_rdx_keys();
+        let pos_vals = PosVals::new_trivial(&sk);
+
+        let xor =
+            BoolCircuit::from_instr(&["y0 = x0 ^ x1"], &wire_names("x", 2), &wire_names("y", 1))
+                .unwrap();
+        let idx = ck.encrypt(0u8);
+        assert!(matches!(
+            byte_circuit_idx(&idx, &xor, &pos_vals, &sk).unwrap_err(),
+            AesFheError::InvalidCircuit(_)
+        ));
+
+        let dir = test_dir("error");
+        let path = dir.join("garbage.bin");
+        fs::write(&path, [0xffu8; 64]).unwrap();
+
+        let err = load_rdx_vec(&path).unwrap_err();
+        println!("{}", err);
+        assert!(matches!(err, AesFheError::Deserialize(_)));
+        assert!(matches!(
+            load_server_key(&path).err(),
+            Some(AesFheError::Deserialize(_))
+        ));
+        assert!(matches!(
+            load_rdx_vec(&dir.join("missing.bin")).unwrap_err(),
+            AesFheError::Io(_)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{
+    dec_rdx_vec, enc_round_keys_fhe, enc_state_fhe, gen_rdx_keys, load_client_key, load_pos_vals,
+    load_rdx_vec, load_server_key, save_client_key, save_pos_vals, save_rdx_vec, save_server_key,
+};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::PosVals;
+pub use aes128_rdx_bc_fhe::aes128_keyschedule::key_expansion;
+
+mod common;
+pub use common::test_dir;
+
+use std::fs;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load_keys_and_state() {
+        let dir = test_dir("keys_and_state");
+
+        let state: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let key: [u8; 16] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+        let xk = key_expansion(&key);
+
+        // client process
+        let (ck, sk) = gen_rdx_keys();
+        save_client_key(&dir.join("client.key"), &ck).unwrap();
+        save_server_key(&dir.join("server.key"), &sk).unwrap();
+        save_pos_vals(&dir.join("pos_vals.bin"), &PosVals::new(&ck)).unwrap();
+        save_rdx_vec(&dir.join("state.bin"), &enc_state_fhe(&state, &ck)).unwrap();
+        save_rdx_vec(&dir.join("xk.bin"), &enc_round_keys_fhe(&xk, &ck).unwrap()).unwrap();
+
+        // server process
+        let sk = load_server_key(&dir.join("server.key")).unwrap();
+        let pos_vals = load_pos_vals(&dir.join("pos_vals.bin")).unwrap();
+        let state_ck = load_rdx_vec(&dir.join("state.bin")).unwrap();
+        let xk_ck = load_rdx_vec(&dir.join("xk.bin")).unwrap();
+        let xor_ck: Vec<_> = state_ck
+            .iter()
+            .zip(xk_ck.iter())
+            .map(|(a, b)| sk.unchecked_bitxor(a, b))
+            .collect();
+        save_rdx_vec(&dir.join("result.bin"), &xor_ck).unwrap();
+
+        // client process
+        let ck = load_client_key(&dir.join("client.key")).unwrap();
+        let result = dec_rdx_vec(&load_rdx_vec(&dir.join("result.bin")).unwrap(), &ck);
+        for i in 0..16 {
+            assert_eq!(result[i], state[i] ^ xk[i]);
+        }
+        assert_eq!(dec_rdx_vec(&xk_ck, &ck), xk);
+        assert_eq!(
+            dec_rdx_vec(pos_vals.get_p_b(), &ck),
+            [1, 2, 4, 8, 16, 32, 64, 128]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_truncated_state() {
+        let dir = test_dir("truncated");
+
+        let (ck, _) = gen_rdx_keys();
+        let path = dir.join("state.bin");
+        save_rdx_vec(&path, &enc_state_fhe(&[0u8; 16], &ck)).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+
+        assert!(load_rdx_vec(&path).is_err());
+        assert!(load_rdx_vec(&dir.join("missing.bin")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::gen_rdx_keys;
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::{
+    INV_MIX_COLS_CIRCUIT, MIX_COLS_CIRCUIT, SBOX_CIRCUIT, SBOX_INV_CIRCUIT,
+};
+pub use aes128_rdx_bc_fhe::aes128_ref::inv_mix_columns;
+pub use aes128_rdx_bc_fhe::aes128_tables::{GMUL2, GMUL3, SBOX};
+pub use aes128_rdx_bc_fhe::bool_circ::{BoolCircuit, Gate, wire_names};
+pub use aes128_rdx_bc_fhe::error::AesFheError;
+
+mod common;
+pub use common::test_dir;
+
+pub fn msb_first(byte: u8) -> Vec<bool> {
+    (0..8).map(|i| (byte >> (7 - i)) & 1 == 1).collect()
+}
+
+pub fn from_msb_first(bits: &[bool]) -> u8 {
+    bits.iter().fold(0, |acc, &b| (acc << 1) | b as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sbox_circuit_plain() {
+        for x in 0..=255u8 {
+            let out = SBOX_CIRCUIT.eval_plain(&msb_first(x)).unwrap();
+            assert_eq!(from_msb_first(&out), SBOX[x as usize]);
+
+            let inv = SBOX_INV_CIRCUIT.eval_plain(&out).unwrap();
+            assert_eq!(from_msb_first(&inv), x);
+        }
+        println!("sbox depth {}", SBOX_CIRCUIT.depth());
+        println!("sbox_inv depth {}", SBOX_INV_CIRCUIT.depth());
+    }
+
+    #[test]
+    fn test_mix_cols_circuit_plain() {
+        let cols: [[u8; 4]; 3] = [
+            [0xdb, 0x13, 0x53, 0x45],
+            [0xf2, 0x0a, 0x22, 0x5c],
+            [0x2d, 0x26, 0x31, 0x4c],
+        ];
+
+        for col in cols {
+            // rows lsb first
+            let inp: Vec<bool> = col
+                .iter()
+                .flat_map(|&b| (0..8).map(move |i| (b >> i) & 1 == 1))
+                .collect();
+            let out = MIX_COLS_CIRCUIT.eval_plain(&inp).unwrap();
+
+            for r in 0..4 {
+                let expected = GMUL2[col[r] as usize]
+                    ^ GMUL3[col[(r + 1) % 4] as usize]
+                    ^ col[(r + 2) % 4]
+                    ^ col[(r + 3) % 4];
+                let byte = (0..8).fold(0u8, |acc, i| acc | ((out[8 * r + i] as u8) << i));
+                assert_eq!(byte, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inv_mix_cols_circuit_plain() {
+        // only XOR gates, so the circuit is linear over GF(2) and the 32 unit
+        // vectors, bit i of byte r at wire 8 * r + i, check it exhaustively
+        for bit in 0..32 {
+            let inp: Vec<bool> = (0..32).map(|i| i == bit).collect();
+            let out = INV_MIX_COLS_CIRCUIT.eval_plain(&inp).unwrap();
+
+            let mut state = [0u8; 16];
+            state[bit / 8] = 1 << (bit % 8);
+            inv_mix_columns(&mut state);
+
+            for r in 0..4 {
+                let byte = (0..8).fold(0u8, |acc, i| acc | ((out[8 * r + i] as u8) << i));
+                assert_eq!(byte, state[r], "unit vector {}", bit);
+            }
+        }
+
+        let xors = INV_MIX_COLS_CIRCUIT
+            .gates()
+            .iter()
+            .filter(|gate| matches!(gate, Gate::Xor(..)))
+            .count();
+        assert_eq!(xors, INV_MIX_COLS_CIRCUIT.gates().len());
+        println!(
+            "inv_mix_cols xors {} depth {}",
+            xors,
+            INV_MIX_COLS_CIRCUIT.depth()
+        );
+    }
+
+    #[test]
+    fn test_circuit_from_instr_unordered() {
+        let instr = ["c = a & b", "a = x0 ^ x1", "d = c !", "b = x1 ^ x2"];
+        let circuit =
+            BoolCircuit::from_instr(&instr, &wire_names("x", 3), &["d".to_string()]).unwrap();
+
+        assert_eq!(circuit.depth(), 3);
+        assert_eq!(circuit.gates().len(), 4);
+        for x in 0..8u8 {
+            let inp: Vec<bool> = (0..3).map(|i| (x >> i) & 1 == 1).collect();
+            let expected = !((inp[0] ^ inp[1]) & (inp[1] ^ inp[2]));
+            assert_eq!(circuit.eval_plain(&inp).unwrap(), vec![expected]);
+        }
+    }
+
+    #[test]
+    fn test_circuit_eval_fhe() {
+        let (ck, sk) = gen_rdx_keys();
+        let circuit = BoolCircuit::new(
+            2,
+            vec![Gate::Xor(0, 1), Gate::And(0, 1), Gate::Not(2)],
+            vec![2, 3, 4],
+        )
+        .unwrap();
+
+        for x in 0..4u8 {
+            let inp = [x & 1 == 1, x & 2 == 2];
+            let inp_ck: Vec<_> = inp
+                .iter()
+                .map(|&b| sk.create_trivial_boolean_block(b))
+                .collect();
+
+            let out: Vec<bool> = circuit
+                .eval(&inp_ck, &sk)
+                .unwrap()
+                .iter()
+                .map(|b| ck.decrypt_bool(b))
+                .collect();
+            assert_eq!(out, circuit.eval_plain(&inp).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_bristol_parse() {
+        // out0 = !(a & b), out1 = c ^ 1, out2 = a & c, out3 = b & c
+        let text = "6 10\n1 3\n1 4\n\n2 1 0 1 3 AND\n1 1 3 6 INV\n1 1 1 4 EQ\n2 1 2 4 7 XOR\n4 2 0 1 2 2 8 9 MAND\n1 1 8 5 EQW\n";
+        let circuit = BoolCircuit::parse_bristol(text).unwrap();
+
+        assert_eq!(circuit.input_sizes(), &[3]);
+        assert_eq!(circuit.output_sizes(), &[4]);
+        for x in 0..8u8 {
+            let inp: Vec<bool> = (0..3).map(|i| (x >> i) & 1 == 1).collect();
+            let (a, b, c) = (inp[0], inp[1], inp[2]);
+            assert_eq!(
+                circuit.eval_plain(&inp).unwrap(),
+                vec![!(a & b), !c, a & c, b & c]
+            );
+        }
+    }
+
+    #[test]
+    fn test_bristol_roundtrip_sbox() {
+        let circuit = BoolCircuit::parse_bristol(&SBOX_CIRCUIT.to_bristol()).unwrap();
+
+        assert_eq!(circuit.depth(), SBOX_CIRCUIT.depth());
+        for x in 0..=255u8 {
+            let out = circuit.eval_plain(&msb_first(x)).unwrap();
+            assert_eq!(from_msb_first(&out), SBOX[x as usize]);
+        }
+    }
+
+    #[test]
+    fn test_circuit_load() {
+        let dir = test_dir("circuits");
+
+        let bristol = dir.join("sbox_inv.txt");
+        std::fs::write(&bristol, SBOX_INV_CIRCUIT.to_bristol()).unwrap();
+        let instr = dir.join("xor.txt");
+        std::fs::write(&instr, "# a xor\n\ny0 = x0 ^ x1\n").unwrap();
+
+        let sbox_inv = BoolCircuit::load(&bristol, &[], &[]).unwrap();
+        for x in 0..=255u8 {
+            let out = sbox_inv.eval_plain(&msb_first(SBOX[x as usize])).unwrap();
+            assert_eq!(from_msb_first(&out), x);
+        }
+
+        let xor = BoolCircuit::load(&instr, &wire_names("x", 2), &wire_names("y", 1)).unwrap();
+        assert_eq!(xor.eval_plain(&[true, false]).unwrap(), vec![true]);
+        assert_eq!(xor.eval_plain(&[true, true]).unwrap(), vec![false]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_circuit_parse_errors() {
+        let x = wire_names("x", 2);
+        let y = wire_names("y", 1);
+
+        let err = |instr: &[&str]| BoolCircuit::from_instr(instr, &x, &y).unwrap_err();
+        assert!(matches!(
+            err(&["y0 = x0 | x1"]),
+            AesFheError::MalformedInstruction(_)
+        ));
+        assert!(matches!(err(&["y0 = x0 ^ x2"]), AesFheError::MissingWire(w) if w == "x2"));
+        assert!(matches!(
+            err(&["y0 = t0 !", "t0 = y0 !"]),
+            AesFheError::InvalidCircuit(_)
+        ));
+        assert!(matches!(
+            err(&["y0 = x0 !", "y0 = x1 !"]),
+            AesFheError::InvalidCircuit(_)
+        ));
+        assert!(matches!(err(&["t0 = x0 !"]), AesFheError::MissingWire(w) if w == "y0"));
+
+        let err = |text: &str| BoolCircuit::parse_bristol(text).unwrap_err();
+        assert!(matches!(
+            err("1 3\n1 2\n1 1\n2 1 0 1 2 OR"),
+            AesFheError::MalformedInstruction(_)
+        ));
+        assert!(matches!(
+            err("1 3\n1 2\n1 1\n2 1 0 5 2 XOR"),
+            AesFheError::MissingWire(_)
+        ));
+        assert!(matches!(
+            err("2 3\n1 2\n1 1\n2 1 0 1 2 XOR"),
+            AesFheError::InvalidCircuit(_)
+        ));
+        assert!(matches!(
+            err("1 3\n2 2\n1 1\n2 1 0 1 2 XOR"),
+            AesFheError::MalformedInstruction(_)
+        ));
+
+        let dir = test_dir("missing");
+        assert!(matches!(
+            BoolCircuit::load(&dir.join("missing.circ"), &x, &y).unwrap_err(),
+            AesFheError::Io(_)
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_circuit_width_errors() {
+        assert!(matches!(
+            BoolCircuit::new(2, vec![Gate::Xor(0, 3), Gate::Not(0)], vec![2]).unwrap_err(),
+            AesFheError::InvalidCircuit(_)
+        ));
+        assert!(matches!(
+            BoolCircuit::new(2, vec![Gate::Xor(0, 1)], vec![3]).unwrap_err(),
+            AesFheError::MissingWire(_)
+        ));
+
+        // a loaded 8 bit circuit does not fit the 32 bit MixColumns
+        let sbox = BoolCircuit::parse_bristol(&SBOX_CIRCUIT.to_bristol()).unwrap();
+        assert!(matches!(
+            sbox.compose(&MIX_COLS_CIRCUIT).unwrap_err(),
+            AesFheError::InvalidCircuit(_)
+        ));
+        assert_eq!(
+            sbox.compose(&SBOX_INV_CIRCUIT)
+                .unwrap()
+                .eval_plain(&msb_first(0x53))
+                .unwrap(),
+            msb_first(0x53)
+        );
+
+        assert!(matches!(
+            sbox.eval_plain(&[false; 7]).unwrap_err(),
+            AesFheError::InvalidCircuit(_)
+        ));
+
+        let (_, sk) = gen_rdx_keys();
+        let inp: Vec<_> = (0..7)
+            .map(|_| sk.create_trivial_boolean_block(false))
+            .collect();
+        assert!(matches!(
+            sbox.eval(&inp, &sk).unwrap_err(),
+            AesFheError::InvalidCircuit(_)
+        ));
+    }
+}
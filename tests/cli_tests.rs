@@ -0,0 +1,37 @@
+use std::process::{Command, Output};
+
+// the client commands reject their arguments before any key is loaded
+pub fn run_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aes128_rdx_bc_fhe"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_malformed_hex() {
+        for hex in ["aé0", "é0", "0g", "abc", "00 11", "+1"] {
+            for command in ["encrypt-key", "encrypt-state"] {
+                let flag = if command == "encrypt-key" { "-k" } else { "-i" };
+                let out = run_cli(&[command, "--client-key", "/nonexistent", flag, hex]);
+                let stderr = String::from_utf8_lossy(&out.stderr);
+                println!("{} {} {}: {}", command, flag, hex, stderr.trim());
+
+                // a clap usage error, not a panic
+                assert_eq!(out.status.code(), Some(2), "{} {}", command, hex);
+                assert!(stderr.contains("invalid value"), "{}", stderr);
+                assert!(!stderr.contains("panicked"), "{}", stderr);
+            }
+        }
+
+        // well formed hex of the wrong length is an error of the command
+        let out = run_cli(&["encrypt-key", "--client-key", "/nonexistent", "-k", "0011"]);
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stderr.contains("KeyLength(2)"), "{}", stderr);
+    }
+}
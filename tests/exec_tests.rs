@@ -0,0 +1,123 @@
+pub use aes128_rdx_bc_fhe::aes_fhe::{dec_state_fhe, enc_state_fhe, gen_rdx_keys};
+pub use aes128_rdx_bc_fhe::aes128_bool_circ::PosVals;
+pub use aes128_rdx_bc_fhe::aes128_rdx_fhe::{add_round_key_fhe, shift_rows_fhe, sub_bytes_fhe};
+pub use aes128_rdx_bc_fhe::aes128_ref::{add_round_key, shift_rows};
+pub use aes128_rdx_bc_fhe::aes128_sbox::SboxBackend;
+pub use aes128_rdx_bc_fhe::exec::ExecContext;
+
+use tfhe::integer::ServerKey;
+use tfhe::integer::ciphertext::BaseRadixCiphertext;
+use tfhe::shortint::Ciphertext;
+
+use rayon::prelude::*;
+
+use std::sync::Mutex;
+
+// an identity S-box that notes the thread and pool size of every call
+#[derive(Default)]
+pub struct PoolProbe {
+    seen: Mutex<Vec<(String, usize)>>,
+}
+
+impl PoolProbe {
+    fn record(&self, idx: &BaseRadixCiphertext<Ciphertext>) -> BaseRadixCiphertext<Ciphertext> {
+        let name = std::thread::current().name().unwrap_or("").to_string();
+        self.seen
+            .lock()
+            .unwrap()
+            .push((name, rayon::current_num_threads()));
+        idx.clone()
+    }
+}
+
+impl SboxBackend for PoolProbe {
+    fn sbox(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        _: &PosVals,
+        _: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        self.record(idx)
+    }
+
+    fn sbox_inv(
+        &self,
+        idx: &BaseRadixCiphertext<Ciphertext>,
+        _: &PosVals,
+        _: &ServerKey,
+    ) -> BaseRadixCiphertext<Ciphertext> {
+        self.record(idx)
+    }
+}
+
+pub fn thread_names(ctx: &ExecContext) -> Vec<String> {
+    ctx.install(|| {
+        (0..64)
+            .into_par_iter()
+            .map(|_| std::thread::current().name().unwrap_or("").to_string())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_threads() {
+        let ctx = ExecContext::new(2).unwrap();
+        assert_eq!(ctx.threads(), 2);
+        assert_eq!(ctx.install(rayon::current_num_threads), 2);
+        assert!(
+            thread_names(&ctx)
+                .iter()
+                .all(|name| name.starts_with("aes-fhe-"))
+        );
+
+        // a clone shares the pool
+        let clone = ctx.clone();
+        assert!(std::ptr::eq(clone.pool(), ctx.pool()));
+
+        let ctx = ExecContext::new(0).unwrap();
+        assert!(ctx.threads() >= 1);
+    }
+
+    #[test]
+    fn test_exec_fhe_step() {
+        let ctx = ExecContext::new(1).unwrap();
+        let (ck, sk) = gen_rdx_keys();
+
+        let mut state: [u8; 16] = rand::random();
+        let rkey: [u8; 16] = rand::random();
+        let mut state_ck = enc_state_fhe(&state, &ck);
+        let rkey_ck = enc_state_fhe(&rkey, &ck);
+
+        ctx.install(|| {
+            add_round_key_fhe(&mut state_ck, &rkey_ck, &sk).unwrap();
+            shift_rows_fhe(&mut state_ck).unwrap();
+        });
+
+        add_round_key(&mut state, &rkey);
+        shift_rows(&mut state);
+        assert_eq!(dec_state_fhe(&state_ck, &ck).unwrap(), state);
+
+        // the parallel loop of a step runs on the installed pool, the probe
+        // sees its threads and size, not those of the global pool
+        let ctx = ExecContext::new(3).unwrap();
+        let pos_vals = PosVals::new_trivial(&sk);
+        let probe = PoolProbe::default();
+        ctx.install(|| sub_bytes_fhe(&mut state_ck, &probe, &pos_vals, &sk));
+
+        let seen = probe.seen.into_inner().unwrap();
+        assert_eq!(seen.len(), 16);
+        assert!(
+            seen.iter()
+                .all(|(name, threads)| name.starts_with("aes-fhe-") && *threads == 3)
+        );
+
+        let probe = PoolProbe::default();
+        sub_bytes_fhe(&mut state_ck, &probe, &pos_vals, &sk);
+        let seen = probe.seen.into_inner().unwrap();
+        assert!(seen.iter().all(|(name, _)| !name.starts_with("aes-fhe-")));
+    }
+}
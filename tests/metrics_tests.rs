@@ -0,0 +1,76 @@
+pub use aes128_rdx_bc_fhe::metrics::{Metrics, OpStats};
+
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_rounds() {
+        let mut metrics = Metrics::new();
+
+        let res = metrics.run(|m| {
+            m.round(|m| m.time("add_round_key", || 1));
+            for _ in 0..2 {
+                m.round(|m| {
+                    m.time("sub_bytes", || ());
+                    m.time("add_round_key", || 2)
+                });
+            }
+            3
+        });
+
+        assert_eq!(res, 3);
+        assert_eq!(metrics.get("add_round_key").count, 3);
+        assert_eq!(metrics.get("sub_bytes").count, 1 + 1);
+        assert_eq!(metrics.get("mix_columns"), OpStats::default());
+
+        let steps: Vec<usize> = metrics.rounds.iter().map(|r| r.count).collect();
+        assert_eq!(steps, vec![1, 2, 2]);
+        assert_eq!(metrics.total.count, 5);
+        assert_eq!(metrics.steps(), 5);
+    }
+
+    #[test]
+    fn test_metrics_merge() {
+        let mut a = Metrics::new();
+        a.record("sub_bytes", Duration::from_millis(10), 4);
+        a.run(|m| m.round(|m| m.record("shift_rows", Duration::ZERO, 0)));
+
+        let mut b = Metrics::new();
+        b.record("sub_bytes", Duration::from_millis(5), 2);
+
+        a.merge(&b);
+        let s = a.get("sub_bytes");
+        assert_eq!((s.count, s.pbs, s.time), (2, 6, Duration::from_millis(15)));
+        assert_eq!(a.rounds.len(), 1);
+        assert_eq!(a.total.count, 1);
+
+        let table = a.to_string();
+        assert!(table.contains("sub_bytes"));
+        assert!(table.contains("round 0"));
+        assert!(table.lines().last().unwrap().starts_with("total"));
+    }
+
+    #[test]
+    fn test_metrics_nest() {
+        let mut inner = Metrics::new();
+        inner.run(|m| {
+            m.round(|m| m.record("add_round_key", Duration::from_millis(3), 1));
+        });
+
+        // the inner run happens inside the outer one, its total is not added twice
+        let mut outer = Metrics::new();
+        outer.run(|m| {
+            m.nest(&inner);
+            m.record("keystream_xor", Duration::from_millis(1), 0);
+        });
+
+        assert_eq!(outer.get("add_round_key").count, 1);
+        assert_eq!(outer.get("keystream_xor").count, 1);
+        assert_eq!(outer.rounds.len(), 1);
+        assert_eq!(outer.total.count, 2);
+        assert_eq!(outer.total.count, outer.steps());
+    }
+}